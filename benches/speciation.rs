@@ -0,0 +1,21 @@
+// Cost of single birth's speciation in continous mode, for different population sizes.
+// It should stay roughly flat, as species sizes and representatives are cached.
+// cargo bench --bench speciation
+
+use std::{hint::black_box, time::Instant};
+use rusty_neat::{ActFunc, NeatContinous, NN};
+
+fn main() {
+    let nn = NN::new(4, 2, None, true, 0.75, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+    for size in [100, 1000, 10000] {
+        let mut h = NeatContinous::new(&nn, size, 8);
+        for _ in 0..5 { h.speciate(); }
+        let keys: Vec<usize> = h.agents.keys().cloned().collect();
+        let children: Vec<usize> = (0..1000).map(|i| h.offspring(&keys[i % keys.len()]) ).collect();
+
+        let start = Instant::now();
+        children.iter().for_each(|c| { black_box(h.species_assign(c)); } );
+        let per_birth = start.elapsed() / children.len() as u32;
+        println!("population {:>6}, species {:>3}: {:?} per species_assign", size, h.species_table.len(), per_birth);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{NeatIntermittent, NN};
+
+/// Handle to agent awaiting evaluation.
+/// It's valid only in generation it was asked in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ask {
+    /// Agent's index in population.
+    pub index: usize,
+    /// Handler's generation.
+    pub generation: usize,
+}
+
+// ask/tell mode, for populations evaluated by external loop (eg. simulator stepped by user)
+impl NeatIntermittent {
+    /// Returns handles to all agents that didn't receive fitness yet in current generation.
+    pub fn ask(&mut self) -> Vec<Ask> {
+        // agents inserted mid-generation are appended, told ones are kept
+        self.told.resize(self.agents.len(), false);
+        self.told.iter().enumerate().filter(|(_, t)| !**t )
+            .map(|(index, _)| Ask { index, generation: self.generation } ).collect()
+    }
+
+    /// Gets agent behind handle, eg. to run it's network.
+    /// Panics if handle is from other generation.
+    pub fn asked(&mut self, handle: &Ask) -> &mut NN {
+        if handle.generation != self.generation {panic!("Handle from generation {} used in {}", handle.generation, self.generation)}
+        &mut self.agents[handle.index]
+    }
+
+    /// Records agent's fitness, handles can be told in any order.
+    /// After every agent is told, next generation is created (speciate, next_gen, mutate) and true is returned.
+    /// Panics if handle is from other generation, or agent was already told.
+    pub fn tell(&mut self, handle: Ask, fitness: f32) -> bool {
+        if handle.generation != self.generation {panic!("Handle from generation {} used in {}", handle.generation, self.generation)}
+        self.told.resize(self.agents.len(), false);
+        if self.told[handle.index] {panic!("Agent {} was already told", handle.index)}
+
+        self.agents[handle.index].fitness = fitness;
+        self.told[handle.index] = true;
+        if self.told.iter().any(|t| !*t ) {return false}
+
+        self.speciate();
+        self.next_gen();
+        self.mutate(None);
+        self.told = vec![false; self.agents.len()];
+        true
+    }
+
+    /// Amount of agents still awaiting fitness in current generation.
+    pub fn pending(&self) -> usize {
+        self.agents.len() - self.told.iter().take(self.agents.len()).filter(|t| **t ).count()
+    }
+}
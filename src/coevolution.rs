@@ -0,0 +1,96 @@
+use itertools::Itertools;
+use rand::seq::IteratorRandom;
+use rayon::prelude::*;
+
+use crate::{NeatIntermittent, NN};
+
+/// Match between two networks, implemented by user.
+pub trait Game: Sync {
+    /// Plays single match, returns score of each player (higher is better).
+    fn play(&self, a: &mut NN, b: &mut NN) -> (f32, f32);
+}
+
+// opponent is either agent (population, index) or archived network that doesn't receive score
+enum Opponent<'a> {
+    Agent(usize, usize),
+    Archived(&'a NN),
+}
+
+/**
+Competitive co-evolution of two (or more) populations, where fitness comes from matches against each other.
+Each agent plays against agents of every other population (all of them, or random sample),
+and optionally against their hall of fame, which prevents forgetting of old strategies.
+Fitness is average score of all matches agent took part in.
+*/
+pub struct Coevolution {
+    pub populations: Vec<NeatIntermittent>,
+    /// Amount of random opponents from each other population, None means all of them.
+    pub sample: Option<usize>,
+    /// Amount of best hall of fame networks of each other population used as additional opponents.
+    /// Populations' hall of fame needs to be enabled.
+    pub hall_of_fame: usize,
+    pub round: usize,
+}
+
+impl Coevolution {
+    pub fn new(populations: Vec<NeatIntermittent>) -> Self {
+        Self { populations, sample: None, hall_of_fame: 0, round: 0 }
+    }
+
+    /// Plays all scheduled matches in parallel and assigns average scores as fitness.
+    pub fn evaluate<G: Game>(&mut self, game: &G) {
+        let mut rng = rand::rng();
+        let mut matches: Vec<((usize, usize), Opponent)> = vec![];
+        for (p, population) in self.populations.iter().enumerate() {
+            for (o, other) in self.populations.iter().enumerate().filter(|(o, _)| *o != p ) {
+                for i in 0..population.agents.len() {
+                    let opponents: Vec<usize> = match self.sample {
+                        Some(n) => (0..other.agents.len()).choose_multiple(&mut rng, n),
+                        None => (0..other.agents.len()).collect(),
+                    };
+                    opponents.into_iter().for_each(|j| matches.push(((p, i), Opponent::Agent(o, j))) );
+                    other.hall_of_fame.genomes.iter().take(self.hall_of_fame)
+                        .for_each(|g| matches.push(((p, i), Opponent::Archived(g))) );
+                }
+            }
+        }
+        // with full schedule each pair would be played twice, once from each side
+        if self.sample.is_none() {
+            matches.retain(|((p, _), o)| match o { Opponent::Agent(op, _) => p < op, Opponent::Archived(_) => true });
+        }
+
+        let agents: Vec<&[NN]> = self.populations.iter().map(|p| p.agents.as_slice() ).collect();
+        let results: Vec<(f32, f32)> = matches.par_iter().map(|((p, i), o)| {
+            let mut a = agents[*p][*i].clone();
+            let mut b = match o {
+                Opponent::Agent(op, j) => agents[*op][*j].clone(),
+                Opponent::Archived(g) => (*g).clone(),
+            };
+            game.play(&mut a, &mut b)
+        }).collect();
+
+        let mut scores: Vec<Vec<(f32, usize)>> = self.populations.iter().map(|p| vec![(0., 0); p.agents.len()] ).collect();
+        matches.iter().zip(results).for_each(|(((p, i), o), (sa, sb))| {
+            scores[*p][*i].0 += sa;
+            scores[*p][*i].1 += 1;
+            if let Opponent::Agent(op, j) = o {
+                scores[*op][*j].0 += sb;
+                scores[*op][*j].1 += 1;
+            }
+        });
+        self.populations.iter_mut().zip(scores).for_each(|(p, s)| {
+            p.agents.iter_mut().zip_eq(s).for_each(|(a, (sum, n))| a.fitness = if n > 0 { sum / n as f32 } else { 0. } );
+        });
+    }
+
+    /// Single round: matches, then speciation, reproduction and mutation of every population.
+    pub fn step<G: Game>(&mut self, game: &G) {
+        self.evaluate(game);
+        self.populations.par_iter_mut().for_each(|p| {
+            p.speciate();
+            p.next_gen();
+            p.mutate(None);
+        });
+        self.round += 1;
+    }
+}
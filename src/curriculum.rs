@@ -0,0 +1,124 @@
+use std::time::Instant;
+
+use crate::{run::{Problem, Stop, Termination}, ActFunc, NeatIntermittent, Population, NN};
+
+/// Single stage of curriculum, with it's own task and I/O size.
+pub struct Stage {
+    pub name: String,
+    /// Amount of inputs (without bias) and outputs networks have in this stage.
+    pub inputs: usize,
+    pub outputs: usize,
+    /// Activation function of outputs added in this stage.
+    pub output_func: ActFunc,
+    /// Fitness function, used by NeatIntermittent::run_curriculum.
+    /// It also does stage's input mapping: it runs networks itself, so it feeds them only inputs they have in this stage.
+    pub problem: Box<dyn Problem>,
+    /// When stage is finished, generations, time and stagnation are counted from it's start.
+    pub advance: Termination,
+}
+
+impl Stage {
+    pub fn new(name: &str, inputs: usize, outputs: usize, problem: impl Problem + 'static, advance: Termination) -> Self {
+        Self { name: name.to_string(), inputs, outputs, output_func: ActFunc::Sigmoid, problem: Box::new(problem), advance }
+    }
+}
+
+/// Logged change of stage.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// Generation or tick of the change.
+    pub time: usize,
+    pub from: usize,
+    /// None if the last stage was finished.
+    pub to: Option<usize>,
+    pub reason: Stop,
+    /// Champion's fitness at the end of stage.
+    pub fitness: f32,
+}
+
+/**
+Expands inputs and outputs of population gradually, when champion crosses fitness threshold
+or stops improving in current stage. Population needs enough reserved space (size_free) for the largest stage.
+*/
+pub struct Curriculum {
+    pub stages: Vec<Stage>,
+    pub current: usize,
+    /// Every stage change so far.
+    pub log: Vec<Transition>,
+    best: Option<f32>,
+    stagnation: usize,
+    start: (usize, Instant),
+}
+
+impl Curriculum {
+    pub fn new(stages: Vec<Stage>) -> Self {
+        Self { stages, current: 0, log: vec![], best: None, stagnation: 0, start: (0, Instant::now()) }
+    }
+
+    pub fn stage(&self) -> &Stage {
+        &self.stages[self.current]
+    }
+
+    /// True after the last stage was finished.
+    pub fn finished(&self) -> bool {
+        self.log.last().is_some_and(|t| t.to.is_none() )
+    }
+
+    /// Adds inputs and outputs, so population matches current stage, and starts counting stage's time from now.
+    /// Should be called before first update (run_curriculum does it), time is the first generation (or tick) of stage.
+    /// Panics if there is no reserved space left.
+    pub fn prepare<P: Population>(&mut self, population: &mut P, time: usize) {
+        self.start = (time, Instant::now());
+        self.expand(population);
+    }
+
+    fn expand<P: Population>(&self, population: &mut P) {
+        let stage = self.stage();
+        let size = population.agents().next().unwrap().size;
+        (size.0 - 1..stage.inputs).for_each(|_| population.add_input() );
+        (size.1..stage.outputs).for_each(|_| population.add_output(&stage.output_func) );
+    }
+
+    /// Checks champion of evaluated population against current stage's criteria.
+    /// If stage is finished, population is expanded to the next one, and transition is logged and returned.
+    /// Time is generation or tick, depending on handler.
+    pub fn update<P: Population>(&mut self, population: &mut P, time: usize) -> Option<Transition> {
+        if self.finished() {return None}
+        let fitness = population.champion()?.fitness;
+        match self.best {
+            Some(b) if b >= fitness => self.stagnation += 1,
+            _ => { self.best = Some(fitness); self.stagnation = 0; }
+        }
+
+        let reason = self.stage().advance.check(self.best.unwrap(), time.saturating_sub(self.start.0) + 1, self.start.1.elapsed(), self.stagnation)?;
+        let to = (self.current + 1 < self.stages.len()).then_some(self.current + 1);
+        let transition = Transition { time, from: self.current, to, reason, fitness: self.best.unwrap() };
+        self.log.push(transition.clone());
+
+        if let Some(to) = to {
+            self.current = to;
+            self.best = None;
+            self.stagnation = 0;
+            self.prepare(population, time + 1);
+        }
+        Some(transition)
+    }
+}
+
+impl NeatIntermittent {
+    /// Evolves population through curriculum stages, each generation agents are evaluated with current stage's problem.
+    /// Returns the best agent of population, after the last stage is finished.
+    pub fn run_curriculum(&mut self, curriculum: &mut Curriculum) -> NN {
+        curriculum.prepare(self, self.generation);
+        loop {
+            self.evaluate(curriculum.stage().problem.as_ref());
+            if let Some(t) = curriculum.update(self, self.generation) && t.to.is_none() {
+                return Population::champion(self).unwrap().clone()
+            }
+
+            self.speciate();
+            self.next_gen();
+            self.mutate(None);
+        }
+    }
+}
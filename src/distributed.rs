@@ -0,0 +1,145 @@
+use std::{collections::VecDeque, io::{self, Read, Write}, net::{SocketAddr, TcpListener, TcpStream}, sync::Mutex, thread, time::{Duration, Instant}};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{run::Problem, NeatIntermittent, NN};
+
+#[derive(Serialize, Deserialize)]
+struct Job {
+    id: usize,
+    nn: NN,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Reply {
+    id: usize,
+    fitness: f32,
+}
+
+// each message is toml string prefixed with it's length
+fn send<T: Serialize>(stream: &mut TcpStream, msg: &T) -> io::Result<()> {
+    let data = toml::to_string(msg).map_err(io::Error::other)?;
+    stream.write_all(&(data.len() as u64).to_be_bytes())?;
+    stream.write_all(data.as_bytes())
+}
+
+// broken or hostile peer could make receiver allocate any amount of memory
+const MAX_FRAME: u64 = 64 * 1024 * 1024;
+
+fn receive<T: DeserializeOwned>(stream: &mut TcpStream) -> io::Result<T> {
+    let mut len = [0; 8];
+    stream.read_exact(&mut len)?;
+    let len = u64::from_be_bytes(len);
+    if len > MAX_FRAME { return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Message of {} bytes is over the limit", len))) }
+    let mut data = vec![0; len as usize];
+    stream.read_exact(&mut data)?;
+    toml::from_str(&String::from_utf8(data).map_err(io::Error::other)?).map_err(io::Error::other)
+}
+
+/**
+Master side of distributed evaluation, workers are separate processes (see `worker`) connected through TCP.
+Networks are sent one at a time to each worker, if worker doesn't reply within timeout
+or connection breaks, it's dropped and network is re-queued for other workers.
+Workers can join at any time, they are accepted at the start of each evaluation.
+*/
+pub struct Master {
+    listener: TcpListener,
+    workers: Vec<TcpStream>,
+    /// Maximum time for single evaluation, and for waiting on workers if there are none.
+    pub timeout: Duration,
+}
+
+impl Master {
+    pub fn bind(addr: &str, timeout: Duration) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener, workers: vec![], timeout })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Amount of connected workers.
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    // accepts waiting workers, if there are none blocks until timeout
+    fn accept(&mut self) -> io::Result<()> {
+        let start = Instant::now();
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    self.workers.push(stream);
+                    continue;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+            if !self.workers.is_empty() {return Ok(())}
+            if start.elapsed() > self.timeout {return Err(io::Error::new(io::ErrorKind::TimedOut, "No workers connected"))}
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Evaluates networks on workers, returns fitness of each.
+    /// Fails only if all workers are lost and no new one connects within timeout.
+    pub fn evaluate(&mut self, agents: &[NN]) -> io::Result<Vec<f32>> {
+        let queue = Mutex::new((0..agents.len()).collect::<VecDeque<usize>>());
+        let results = Mutex::new(vec![None; agents.len()]);
+        let done = || results.lock().unwrap().iter().all(|r: &Option<f32>| r.is_some() );
+
+        while !done() {
+            self.accept()?;
+            let workers = std::mem::take(&mut self.workers);
+            // each worker is served by own thread, lost ones aren't returned
+            self.workers = thread::scope(|s| {
+                let handles: Vec<_> = workers.into_iter().map(|mut stream| s.spawn(|| {
+                    loop {
+                        let job = queue.lock().unwrap().pop_front();
+                        let id = match job {
+                            Some(id) => id,
+                            None if done() => return Some(stream),
+                            None => { thread::sleep(Duration::from_millis(1)); continue; } // others may still fail
+                        };
+                        let reply = send(&mut stream, &Job { id, nn: agents[id].clone() })
+                            .and_then(|_| receive::<Reply>(&mut stream) );
+                        match reply {
+                            Ok(r) if r.id == id => results.lock().unwrap()[id] = Some(r.fitness),
+                            _ => { queue.lock().unwrap().push_back(id); return None; }
+                        }
+                    }
+                })).collect();
+                handles.into_iter().filter_map(|h| h.join().unwrap() ).collect()
+            });
+        }
+        Ok(results.into_inner().unwrap().into_iter().map(|r| r.unwrap() ).collect())
+    }
+}
+
+/// Worker side of distributed evaluation.
+/// Connects to master, evaluates received networks until master disconnects.
+pub fn worker<P: Problem>(addr: &str, problem: &P) -> io::Result<()> {
+    let mut stream = TcpStream::connect(addr)?;
+    loop {
+        let mut job: Job = match receive(&mut stream) {
+            Ok(j) => j,
+            // master is gone
+            Err(e) if matches!(e.kind(), io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let fitness = problem.evaluate(&mut job.nn);
+        send(&mut stream, &Reply { id: job.id, fitness })?;
+    }
+}
+
+impl NeatIntermittent {
+    /// Evaluates all agents on master's workers, assigning their fitness.
+    pub fn evaluate_distributed(&mut self, master: &mut Master) -> io::Result<()> {
+        let fitness = master.evaluate(&self.agents)?;
+        self.agents.iter_mut().zip(fitness).for_each(|(a, f)| a.fitness = f );
+        Ok(())
+    }
+}
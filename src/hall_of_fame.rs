@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+use serde::{Deserialize, Serialize};
+
+use crate::NN;
+
+/**
+Bounded archive of best networks ever seen, sorted from the best.
+Networks are distinct: if the same structure and weights appears again, only fitter copy is kept.
+Capacity 0 disables it.
+*/
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct HallOfFame {
+    pub capacity: usize,
+    pub genomes: Vec<NN>,
+}
+
+impl HallOfFame {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, genomes: vec![] }
+    }
+
+    /// Offers network to archive, returns true if it was accepted.
+    pub fn insert(&mut self, nn: &NN) -> bool {
+        if self.capacity == 0 {return false}
+        if let Some(p) = self.genomes.iter().position(|g| same(g, nn) ) {
+            if self.genomes[p].fitness >= nn.fitness {return false}
+            self.genomes.remove(p);
+        } else if self.genomes.len() >= self.capacity && self.genomes.last().unwrap().fitness >= nn.fitness {
+            return false
+        }
+        let p = self.genomes.partition_point(|g| g.fitness >= nn.fitness );
+        self.genomes.insert(p, nn.clone());
+        self.genomes.truncate(self.capacity);
+        true
+    }
+
+    /// Offers every network, returns amount of accepted ones.
+    pub fn update(&mut self, agents: &[NN]) -> usize {
+        agents.iter().filter(|a| self.insert(a) ).count()
+    }
+
+    pub fn best(&self) -> Option<&NN> {
+        self.genomes.first()
+    }
+
+    /// Saves every network into directory as `<rank>.toml`.
+    pub fn save(&self, dir: &str) {
+        fs::create_dir_all(dir).unwrap();
+        self.genomes.iter().enumerate().for_each(|(i, g)| g.save(Path::new(dir).join(format!("{}.toml", i)).to_str().unwrap()) );
+    }
+
+    /// Loads every ".toml" network from directory.
+    pub fn load(dir: &str, capacity: usize) -> Self {
+        let mut s = Self::new(capacity);
+        fs::read_dir(dir).unwrap().map(|e| e.unwrap().path() )
+            .filter(|p| p.extension().is_some_and(|e| e == "toml") )
+            .for_each(|p| {
+                let mut nn = NN::new(0, 0, None, false, 0., crate::ActFunc::None, &[]);
+                nn.load(p.to_str().unwrap());
+                s.insert(&nn);
+            });
+        s
+    }
+}
+
+/// Same structure and weights.
+fn same(a: &NN, b: &NN) -> bool {
+    a.connections.len() == b.connections.len() && a.nodes.len() == b.nodes.len() &&
+    a.nodes.iter().all(|(k, n)| b.nodes.get(k).is_some_and(|o| o.act_func == n.act_func) ) &&
+    a.connections.iter().all(|(k, c)| b.connections.get(k).is_some_and(|o|
+        o.from == c.from && o.to == c.to && o.recurrent == c.recurrent && o.gater == c.gater &&
+        o.active == c.active && (o.weight - c.weight).abs() < 1e-6
+    ))
+}
@@ -0,0 +1,48 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Genre, NodeKey, NN};
+
+/// Innovation table's size statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InnovStats {
+    /// Entries in table.
+    pub entries: usize,
+    /// Entries carried by at least one living agent.
+    pub used: usize,
+    /// First free innovation number.
+    pub next_id: usize,
+    /// Rough estimate of table's memory usage in bytes.
+    pub memory: usize,
+}
+
+/// Innovation numbers carried by agents, connections and split connections of hidden nodes.
+fn used<'a>(agents: impl Iterator<Item = &'a NN>) -> HashSet<usize> {
+    let mut used = HashSet::new();
+    agents.for_each(|a| {
+        used.extend(a.connections.keys());
+        used.extend(a.nodes.iter().filter(|(_, n)| n.genre == Genre::Hidden ).map(|(k, _)| k.sconn ));
+    });
+    used
+}
+
+/// Drops entries that no agent carries, returns amount of removed ones.
+/// Ids of living genes stay the same, and removed ones are never reused (innov_id isn't decreased),
+/// so if the same connection appears again it just gets new number.
+pub(crate) fn compact<'a>(innov_table: &mut HashMap<(NodeKey, NodeKey, bool), usize>, agents: impl Iterator<Item = &'a NN>) -> usize {
+    let used = used(agents);
+    let before = innov_table.len();
+    innov_table.retain(|_, id| used.contains(id) );
+    innov_table.shrink_to_fit();
+    before - innov_table.len()
+}
+
+pub(crate) fn stats<'a>(innov_table: &HashMap<(NodeKey, NodeKey, bool), usize>, innov_id: usize, agents: impl Iterator<Item = &'a NN>) -> InnovStats {
+    let used = used(agents);
+    InnovStats {
+        entries: innov_table.len(),
+        used: innov_table.values().filter(|id| used.contains(id) ).count(),
+        next_id: innov_id,
+        // key, value, and hashbrown's control byte
+        memory: innov_table.capacity() * (size_of::<((NodeKey, NodeKey, bool), usize)>() + 1),
+    }
+}
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, time::Instant};
+use itertools::Itertools;
+use rand::seq::IteratorRandom;
+use rayon::prelude::*;
+
+use crate::{innovation::{self, InnovStats}, run::{Problem, Stop, Termination}, Lineage, NeatIntermittent, NodeKey, NN};
+
+/// Which islands receive migrants from given one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    /// To the next island, last one sends to the first.
+    Ring,
+    /// To every other island.
+    Full,
+    /// To single random island, drawn at each migration.
+    Random,
+}
+
+/**
+Island model: several independent NeatIntermittent populations evolving in parallel.
+Every "interval" generations best agents of each island are copied into it's neighbours
+(replacing the worst ones), which slows down convergence of the whole system.
+All islands share single innovation table, so migrants don't need re-keying.
+Islands' own tables are kept empty, shared one is lent to them only during reproduction and mutation,
+along with lineage id counter, so ids are unique across islands.
+Islands' own innov_cap is ignored, as shared table can be compacted only with agents of all islands (see Islands::innov_cap).
+*/
+pub struct Islands {
+    pub islands: Vec<NeatIntermittent>,
+    /// First free innovation number.
+    pub innov_id: usize,
+    /// If connection have the same souce, destination, and recurrency, it has also same id.
+    pub innov_table: HashMap<(NodeKey, NodeKey, bool), usize>,
+    /// If shared innovation table grows above it, unused entries are dropped (see innov_compact).
+    pub innov_cap: Option<usize>,
+    /// Migration happens every "interval" generations.
+    pub interval: usize,
+    /// Amount of best agents sent by each island.
+    pub migrants: usize,
+    pub topology: Topology,
+    pub generation: usize,
+    /// Lineage id of the next agent, shared by all islands.
+    pub next_id: usize,
+}
+
+impl Islands {
+    /// Each island is created like NeatIntermittent::new, but innovations are kept in shared table.
+    pub fn new(agent: &NN, islands: usize, size: usize, species_amount: usize) -> Self {
+        let mut s = Self {
+            islands: vec![],
+            innov_id: agent.size.0+agent.size.1+1+agent.size_free.0+agent.size_free.1,
+            innov_table: HashMap::new(),
+            innov_cap: None,
+            interval: 10,
+            migrants: 1,
+            topology: Topology::Ring,
+            generation: 0,
+            next_id: 0,
+        };
+        for _ in 0..islands {
+            let mut island = NeatIntermittent::new(agent, size, species_amount);
+            island.agents.iter_mut().for_each(|a| a.rekey(&mut s.innov_table, &mut s.innov_id) );
+            island.innov_table.clear();
+            island.agents.iter_mut().for_each(|a| { a.lineage = Lineage::new(s.next_id, None, 0); s.next_id += 1; });
+            s.islands.push(island);
+        }
+        s
+    }
+
+    /// Runs single generation: evaluation, migration (if it's time), speciation, reproduction and mutation.
+    /// Islands are processed in parallel.
+    pub fn step<P: Problem>(&mut self, problem: &P) {
+        self.islands.par_iter_mut().for_each(|i| i.evaluate(problem) );
+        self.evolve();
+    }
+
+    // everything after evaluation
+    fn evolve(&mut self) {
+        self.generation += 1;
+        // migrants are speciated along with the rest, so threshold is corrected once per generation
+        if self.interval > 0 && self.generation.is_multiple_of(self.interval) { self.migrate(); }
+        self.islands.par_iter_mut().for_each(|i| i.speciate() );
+        // reproduction assigns lineage ids and mutation registers innovations, 
+        // so islands take turns with shared counter and table
+        for i in 0..self.islands.len() {
+            // island's own cap would compact shared table with only it's agents
+            let cap = self.islands[i].innov_cap.take();
+            self.lend(i);
+            self.islands[i].next_gen();
+            self.islands[i].mutate(None);
+            self.take_back(i);
+            self.islands[i].innov_cap = cap;
+        }
+        if self.innov_cap.is_some_and(|c| self.innov_table.len() > c) { self.innov_compact(); }
+    }
+
+    /// Copies best agents of each island into it's neighbours, replacing their worst agents.
+    /// Migrants get new lineage ids, with original as both parents.
+    /// Agents need to have fitness assigned.
+    pub fn migrate(&mut self) {
+        let mut rng = rand::rng();
+        let n = self.islands.len();
+        let emigrants: Vec<Vec<NN>> = self.islands.iter().map(|i| {
+            i.agents.iter().sorted_by(|a, b| b.fitness.total_cmp(&a.fitness) ).take(self.migrants).cloned().collect()
+        }).collect();
+
+        let mut arrivals: Vec<Vec<NN>> = vec![vec![]; n];
+        emigrants.into_iter().enumerate().for_each(|(from, agents)| {
+            let targets: Vec<usize> = match self.topology {
+                Topology::Ring => vec![(from + 1) % n],
+                Topology::Full => (0..n).filter(|t| *t != from ).collect(),
+                Topology::Random => (0..n).filter(|t| *t != from ).choose(&mut rng).into_iter().collect(),
+            };
+            targets.iter().filter(|t| **t != from ).for_each(|t| arrivals[*t].extend(agents.iter().cloned()) );
+        });
+
+        self.islands.iter_mut().zip(arrivals).for_each(|(island, arrivals)| {
+            let worst: Vec<usize> = (0..island.agents.len())
+                .sorted_by(|a, b| island.agents[*a].fitness.total_cmp(&island.agents[*b].fitness) ).collect();
+            worst.into_iter().zip(arrivals).for_each(|(w, mut a)| {
+                a.species = 0; // species of other island
+                // copy is new agent, original stays on it's island
+                a.lineage = Lineage::new(self.next_id, Some((a.lineage.id, a.lineage.id)), self.generation);
+                self.next_id += 1;
+                island.agents[w] = a;
+            });
+        });
+    }
+
+    /// Evolves all islands until one of termination criteria is met, see NeatIntermittent::run.
+    /// Returned network is the best one ever seen on any island.
+    pub fn run<P: Problem>(&mut self, problem: &P, termination: &Termination) -> (Stop, NN) {
+        let start = Instant::now();
+        let mut champion: Option<NN> = None;
+        let mut stagnation = 0;
+        let mut generation = 0;
+
+        loop {
+            self.islands.par_iter_mut().for_each(|i| i.evaluate(problem) );
+            generation += 1;
+
+            let best = self.best();
+            match &champion {
+                Some(c) if c.fitness >= best.fitness => stagnation += 1,
+                _ => { champion = Some(best.clone()); stagnation = 0; }
+            }
+            let fitness = champion.as_ref().unwrap().fitness;
+            if let Some(stop) = termination.check(fitness, generation, start.elapsed(), stagnation) {
+                return (stop, champion.unwrap())
+            }
+
+            self.evolve();
+        }
+    }
+
+    /// Agent with highest fitness across all islands.
+    pub fn best(&self) -> &NN {
+        self.islands.iter().flat_map(|i| i.agents.iter() ).max_by(|a, b| a.fitness.total_cmp(&b.fitness) ).unwrap()
+    }
+
+    /// Drops shared innovation table entries that no agent of any island carries, returns amount of removed ones.
+    /// Hall of fame networks are counted as living.
+    pub fn innov_compact(&mut self) -> usize {
+        innovation::compact(&mut self.innov_table, genomes(&self.islands))
+    }
+
+    pub fn innov_stats(&self) -> InnovStats {
+        innovation::stats(&self.innov_table, self.innov_id, genomes(&self.islands))
+    }
+
+    fn lend(&mut self, i: usize) {
+        self.islands[i].innov_table = std::mem::take(&mut self.innov_table);
+        self.islands[i].innov_id = self.innov_id;
+        self.islands[i].next_id = self.next_id;
+    }
+
+    fn take_back(&mut self, i: usize) {
+        self.innov_table = std::mem::take(&mut self.islands[i].innov_table);
+        self.innov_id = self.islands[i].innov_id;
+        self.next_id = self.islands[i].next_id;
+    }
+}
+
+// agents and hall of fame of all islands
+fn genomes(islands: &[NeatIntermittent]) -> impl Iterator<Item = &NN> {
+    islands.iter().flat_map(|i| i.agents.iter().chain(i.hall_of_fame.genomes.iter()) )
+}
@@ -6,10 +6,47 @@ mod connection;
 mod node;
 mod nn;
 mod neat;
+mod run;
+mod ask;
+mod stats;
+mod report;
+mod hall_of_fame;
+mod novelty;
+mod pareto;
+mod map_elites;
+mod islands;
+mod coevolution;
+mod distributed;
+mod innovation;
+mod lifecycle;
+mod lineage;
+mod phylogeny;
+mod threshold;
+mod population;
+mod curriculum;
+mod reshape;
 #[cfg(feature = "visu")]
 mod visu;
 
 pub use neat::{NeatIntermittent, NeatContinous};
+pub use map_elites::NeatMapElites;
+pub use islands::{Islands, Topology};
+pub use coevolution::{Coevolution, Game};
+pub use distributed::{Master, worker};
+pub use innovation::InnovStats;
+pub use lifecycle::{Event, Death, Replacement};
+pub use lineage::{Lineage, Mutation};
+pub use phylogeny::{Phylogeny, Record};
+pub use threshold::{ThresholdControl, ThresholdController};
+pub use population::Population;
+pub use curriculum::{Curriculum, Stage, Transition};
+pub use run::{Problem, Termination, Stop};
+pub use ask::Ask;
+pub use stats::{GenerationStats, SpeciesStats, Observer, percentile};
+pub use report::{Reporter, ReportFormat};
+pub use hall_of_fame::HallOfFame;
+pub use novelty::Novelty;
+pub use pareto::{dominates, non_dominated_sort, crowding_distance};
 pub use nn::NN;
 pub use connection::Connection;
 pub use node::{Node, ActFunc, NodeKey, Genre};
@@ -30,10 +67,267 @@ pub use visu::visu;
 
 #[cfg(test)]
 mod tests {
-    //use super::*;
+    use super::*;
 
     #[test]
     fn it_works() {
         assert_eq!(1, 1);
     }
+
+    struct Size;
+    impl Problem for Size {
+        fn evaluate(&self, nn: &mut NN) -> f32 { nn.connections.len() as f32 }
+    }
+
+    #[test]
+    fn distributed_evaluation_survives_worker_loss() {
+        use std::{io::Read, net::TcpStream, thread, time::Duration};
+
+        let nn = NN::new(2, 1, None, false, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        let mut handler = NeatIntermittent::new(&nn, 20, 2);
+        let mut master = Master::bind("127.0.0.1:0", Duration::from_secs(5)).unwrap();
+        let addr = master.local_addr().unwrap().to_string();
+
+        // only worker at start reads part of the first job and disconnects, proper ones join later
+        let a = addr.clone();
+        let flaky = thread::spawn(move || { let mut s = TcpStream::connect(a).unwrap(); s.read_exact(&mut [0; 4]).unwrap(); });
+        thread::sleep(Duration::from_millis(100));
+        let workers: Vec<_> = (0..2).map(|_| { let a = addr.clone(); thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            worker(&a, &Size).unwrap()
+        }) }).collect();
+
+        handler.evaluate_distributed(&mut master).unwrap();
+        assert!(handler.agents.iter().all(|a| a.fitness == a.connections.len() as f32 ));
+        assert!((1..=2).contains(&master.workers()));
+
+        drop(master);
+        flaky.join().unwrap();
+        workers.into_iter().for_each(|w| w.join().unwrap() );
+    }
+
+    #[test]
+    fn io_reshape_keeps_innovations_consistent() {
+        let mut nn = NN::new(3, 2, Some((1, 1)), true, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        nn.set_chances(&[10, 20, 10, 0, 0, 0, 0, 0]);
+        let mut handler = NeatIntermittent::new(&nn, 30, 3);
+        let evolve = |h: &mut NeatIntermittent| for _ in 0..10 {
+            h.evaluate(&Size); h.speciate(); h.next_gen(); h.mutate(None);
+        };
+        evolve(&mut handler);
+
+        handler.remove_input(1);
+        handler.remove_output(0);
+        handler.grow_reserve(2, 2);
+        (0..3).for_each(|_| handler.add_input() );
+        evolve(&mut handler);
+
+        for a in &handler.agents {
+            assert_eq!((a.size, a.size_free), ((6, 1), (1, 4)));
+            let end = a.size.0 + a.size_free.0 + a.size.1 + a.size_free.1;
+            assert!(a.connections.iter().all(|(id, c)| handler.innov_table.get(&(c.from.clone(), c.to.clone(), c.recurrent)) == Some(id) ));
+            assert!(a.nodes.iter().all(|(k, n)| n.genre != Genre::Hidden || k.sconn > end || k.dup > 0 ));
+        }
+    }
+
+    #[test]
+    fn io_removal_survives_empty_genomes() {
+        let nn = NN::new(3, 1, None, true, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        let mut handler = NeatIntermittent::new(&nn, 30, 3);
+        (0..3).for_each(|_| handler.remove_input(0) );
+        assert!(handler.agents.iter().any(|a| a.connections.is_empty() ));
+        for _ in 0..10 {
+            handler.evaluate(&Size); handler.speciate(); handler.next_gen(); handler.mutate(None);
+        }
+        assert!(handler.agents.iter().all(|a| a.size == (1, 1) ));
+    }
+
+    #[test]
+    #[should_panic(expected = "only output")]
+    fn io_removal_keeps_last_output() {
+        let nn = NN::new(3, 1, None, true, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        NeatIntermittent::new(&nn, 10, 1).remove_output(0);
+    }
+
+    #[test]
+    fn pi_threshold_doesnt_wind_up() {
+        let mut c = ThresholdController::new(ThresholdControl::Pi { kp: 0.5, ki: 0.5 }, 0.5, (0.5, 10.));
+        // too few species for long time, threshold sits at lower bound
+        let mut t = (0..100).fold(3., |t, _| c.update(t, 1, 10) );
+        assert_eq!(t, 0.5);
+        // once there are too many species, threshold starts rising right away
+        t = c.update(t, 20, 10);
+        assert!(t > 0.5);
+    }
+
+    #[test]
+    fn rekey_matches_innovation_table() {
+        let mut nn = NN::new(3, 2, None, true, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        nn.set_chances(&[0, 20, 20, 0, 0, 0, 0, 0]);
+        let mut donor = NeatIntermittent::new(&nn, 20, 3);
+        (0..10).for_each(|_| donor.mutate(None) );
+        let genome = donor.agents.iter().find(|a| a.nodes.values().any(|n| n.genre == Genre::Hidden ) ).unwrap().clone();
+
+        let consistent = |a: &NN, table: &std::collections::HashMap<(NodeKey, NodeKey, bool), usize>|
+            a.connections.iter().all(|(id, c)| table.get(&(c.from.clone(), c.to.clone(), c.recurrent)) == Some(id) );
+        let mut handler = NeatContinous::new(&nn, 20, 3);
+        (0..10).for_each(|_| handler.agents.keys().cloned().collect::<Vec<_>>().iter().for_each(|k| handler.mutate(k) ) );
+        let key = handler.insert_genome(genome.clone());
+        assert!(handler.agents.values().all(|a| consistent(a, &handler.innov_table) ));
+        assert_eq!(handler.agents[&key].connections.len(), genome.connections.len());
+
+        // population emptied by lifecycle still accepts genomes
+        handler.agents.keys().cloned().collect::<Vec<_>>().iter().for_each(|k| { handler.kill(k); });
+        let key = handler.insert_genome(genome);
+        assert!(consistent(&handler.agents[&key], &handler.innov_table));
+    }
+
+    #[test]
+    fn pareto_dominance() {
+        assert!(dominates(&[1., 2.], &[1., 1.]));
+        assert!(!dominates(&[1., 1.], &[1., 2.]));
+        assert!(!dominates(&[1., 1.], &[1., 1.]));
+        assert!(!dominates(&[2., 1.], &[1., 2.]) && !dominates(&[1., 2.], &[2., 1.]));
+    }
+
+    #[test]
+    fn pareto_fronts_and_crowding() {
+        let points: [&[f32]; 7] = [&[1., 5.], &[2., 4.], &[3., 3.], &[4., 2.], &[5., 1.], &[2., 2.], &[1., 1.]];
+        let fronts = non_dominated_sort(&points);
+        assert_eq!(fronts, vec![vec![0, 1, 2, 3, 4], vec![5], vec![6]]);
+
+        // evenly spaced front, each objective adds 2 / 4 to inner points
+        let distance = crowding_distance(&points, &fronts[0]);
+        assert!(distance[0].is_infinite() && distance[4].is_infinite());
+        assert!(distance[1..4].iter().all(|d| (d - 1.).abs() < 1e-6 ));
+        // too small front is all boundary
+        assert!(crowding_distance(&points, &[5, 6]).iter().all(|d| d.is_infinite() ));
+    }
+
+    #[test]
+    fn ask_tell_keeps_results_across_insert() {
+        let nn = NN::new(2, 1, None, false, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        let mut handler = NeatIntermittent::new(&nn, 10, 2);
+        let asks = handler.ask();
+        // out of order
+        [7, 2, 9, 0, 4].iter().for_each(|i| assert!(!handler.tell(asks[*i], 1.)) );
+        assert_eq!(handler.pending(), 5);
+
+        let genome = handler.agents[0].clone();
+        handler.insert_genome(genome);
+        assert_eq!(handler.pending(), 6);
+        let rest = handler.ask();
+        assert_eq!(rest.iter().map(|a| a.index ).collect::<Vec<_>>(), vec![1, 3, 5, 6, 8, 10]);
+
+        let generation = handler.generation;
+        let done = rest.iter().map(|a| handler.tell(*a, 1.) ).collect::<Vec<_>>();
+        assert_eq!(done, vec![false, false, false, false, false, true]);
+        assert_eq!(handler.generation, generation + 1);
+        assert_eq!(handler.pending(), handler.agents.len());
+    }
+
+    #[test]
+    fn io_add_reaches_stored_genomes() {
+        let nn = NN::new(2, 1, Some((2, 2)), false, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        let mut handler = NeatIntermittent::new(&nn, 20, 2);
+        handler.hall_of_fame = HallOfFame::new(3);
+        handler.evaluate(&Size); handler.speciate(); handler.next_gen();
+        handler.add_input();
+        handler.add_output(&ActFunc::Sigmoid);
+        assert!(!handler.hall_of_fame.genomes.is_empty() && handler.hall_of_fame.genomes.iter().all(|g| g.size == (4, 2) ));
+        handler.reintroduce(1);
+
+        let mut handler = NeatContinous::new(&nn, 20, 2);
+        handler.speciate();
+        handler.add_input();
+        let representatives = handler.species_table.values().filter_map(|s| s.representative.as_ref() ).collect::<Vec<_>>();
+        assert!(!representatives.is_empty() && representatives.iter().all(|r| r.size == (4, 1) ));
+    }
+
+    #[test]
+    fn islands_keep_lineage_ids_unique() {
+        use itertools::Itertools;
+
+        let nn = NN::new(2, 1, None, false, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        let mut islands = Islands::new(&nn, 3, 10, 2);
+        islands.topology = Topology::Full;
+        islands.migrants = 2;
+        islands.islands.iter_mut().for_each(|i| i.evaluate(&Size) );
+        islands.migrate();
+
+        let ids = islands.islands.iter().flat_map(|i| i.agents.iter().map(|a| a.lineage.id ) ).collect::<Vec<_>>();
+        assert_eq!(ids.iter().unique().count(), ids.len());
+        assert_eq!(islands.next_id, 30 + 3 * 4);
+    }
+
+    #[test]
+    fn species_assign_moves_agent_between_species() {
+        let nn = NN::new(3, 2, None, false, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        let mut handler = NeatIntermittent::new(&nn, 10, 3);
+        handler.speciate();
+        (0..10).for_each(|i| { handler.species_assign(i); });
+        assert_eq!(handler.species_table.values().map(|s| s.size ).sum::<usize>(), 10);
+        assert!(handler.species_table.iter().all(|(k, s)| s.size == handler.agents.iter().filter(|a| a.species == *k ).count() ));
+    }
+
+    #[test]
+    fn continous_species_sizes_match_recount() {
+        use rand::{seq::IteratorRandom, Rng};
+
+        let nn = NN::new(2, 1, None, false, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        let mut handler = NeatContinous::new(&nn, 30, 4);
+        handler.speciate();
+        handler.lifespan = Some(40);
+        handler.floor = Some(10);
+        handler.cap = Some(50);
+        handler.replacement = Some(Replacement { interval: 5, min_age: 3 });
+
+        let mut rng = rand::rng();
+        for _ in 0..300 {
+            let key = *handler.agents.keys().choose(&mut rng).unwrap();
+            match rng.random_range(0..4) {
+                0 => { handler.offspring(&key); },
+                1 => { let mate = *handler.agents.keys().choose(&mut rng).unwrap(); handler.offspring_with(&key, &mate); },
+                2 => { handler.kill(&key); },
+                _ => {
+                    handler.agents.values_mut().for_each(|a| a.fitness = rng.random_range(0.1..1.) );
+                    let inputs = handler.agents.keys().map(|k| (*k, vec![0.5, 0.5]) ).collect();
+                    handler.forward(&inputs);
+                }
+            }
+            let counted = handler.species_table.iter().map(|(k, s)| (*k, s.size) ).collect::<std::collections::BTreeMap<_, _>>();
+            handler.species_recount();
+            let recounted = handler.species_table.iter().map(|(k, s)| (*k, s.size) ).collect::<std::collections::BTreeMap<_, _>>();
+            assert_eq!(counted, recounted);
+        }
+    }
+
+    #[test]
+    fn innov_compaction_keeps_table_consistent() {
+        let consistent = |a: &NN, table: &std::collections::HashMap<(NodeKey, NodeKey, bool), usize>|
+            a.connections.iter().all(|(id, c)| table.get(&(c.from.clone(), c.to.clone(), c.recurrent)) == Some(id) );
+        let mut nn = NN::new(3, 2, None, true, 0.5, ActFunc::Sigmoid, &[ActFunc::Sigmoid]);
+        nn.set_chances(&[10, 20, 10, 0, 0, 0, 0, 0]);
+
+        let mut handler = NeatIntermittent::new(&nn, 30, 3);
+        handler.innov_cap = Some(20);
+        for _ in 0..20 {
+            handler.evaluate(&Size); handler.speciate(); handler.next_gen(); handler.mutate(None);
+            assert!(handler.agents.iter().all(|a| consistent(a, &handler.innov_table) ));
+        }
+        assert!(handler.innov_stats().entries < handler.innov_id - 8);
+
+        let mut handler = NeatContinous::new(&nn, 30, 3);
+        handler.speciate();
+        handler.innov_cap = Some(20);
+        let mut keys = handler.agents.keys().cloned().collect::<Vec<_>>();
+        for _ in 0..20 {
+            // each agent is replaced by it's offspring, so old genes die out
+            keys.iter().for_each(|k| handler.mutate(k) );
+            keys = keys.iter().map(|k| handler.offspring(k) ).collect();
+            handler.agents.keys().filter(|k| !keys.contains(k) ).cloned().collect::<Vec<_>>().iter().for_each(|k| { handler.kill(k); });
+            assert!(handler.agents.values().all(|a| consistent(a, &handler.innov_table) ));
+        }
+        assert!(handler.innov_stats().entries < handler.innov_id - 8);
+    }
 }
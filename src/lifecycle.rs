@@ -0,0 +1,142 @@
+use itertools::Itertools;
+use rand_distr::{weighted::WeightedIndex, Distribution};
+
+use crate::{NeatContinous, NN};
+
+/// Why agent was removed from NeatContinous.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Death {
+    /// Removed by user with kill.
+    Killed,
+    /// Exceeded lifespan.
+    Age,
+    /// Population was above cap, the least fit agents are removed.
+    Cap,
+    /// Had no input vector in check_integrity.
+    Integrity,
+    /// Worst agent replaced by rtNEAT step (see Replacement).
+    Replaced,
+}
+
+/// Population change in NeatContinous, collected (if enabled with record_events) until drained with events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Parents are None for networks inserted from outside.
+    Birth { key: usize, parents: Option<(usize, usize)> },
+    Death { key: usize, cause: Death },
+}
+
+/**
+rtNEAT loop: every `interval` ticks the worst agent (by adjusted fitness, ie. divided by species size)
+that is at least `min_age` old is removed, and replaced by offspring of species chosen
+proportionally to it's average adjusted fitness.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct Replacement {
+    pub interval: usize,
+    pub min_age: usize,
+}
+
+impl NeatContinous {
+    /// Removes agent, returns it if it existed.
+    pub fn kill(&mut self, key: &usize) -> Option<NN> {
+        self.remove(key, Death::Killed)
+    }
+
+    pub(crate) fn remove(&mut self, key: &usize, cause: Death) -> Option<NN> {
+        let agent = self.agents.remove(key);
+        if let Some(a) = &agent {
+            self.species_leave(a.species);
+            self.event(Event::Death { key: *key, cause });
+        }
+        agent
+    }
+
+    /// Births and deaths are collected only if enabled (disabled by default), 
+    /// as in long runs they pile up if nobody drains them. Disabling drops collected ones.
+    pub fn record_events(&mut self, enabled: bool) {
+        self.events = if enabled { Some(self.events.take().unwrap_or_default()) } else { None };
+    }
+
+    /// Returns births and deaths since last call, empty if recording is disabled.
+    pub fn events(&mut self) -> Vec<Event> {
+        self.events.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    pub(crate) fn event(&mut self, event: Event) {
+        if let Some(events) = &mut self.events { events.push(event); }
+    }
+
+    /// Creates offspring of agent from species with the highest average fitness, and assigns it to species.
+    /// Parent is chosen by fitness. Returns None if population is empty.
+    pub fn spawn(&mut self) -> Option<usize> {
+        let species = self.agents.values().into_group_map_by(|a| a.species ).into_iter()
+            .map(|(s, a)| (s, a.iter().map(|a| a.fitness ).sum::<f32>() / a.len() as f32) )
+            .max_by(|a, b| a.1.total_cmp(&b.1) )?.0;
+        Some(self.breed(species))
+    }
+
+    // offspring of fitness-weighted parent from given species, assigned to species
+    fn breed(&mut self, species: usize) -> usize {
+        let (keys, fs): (Vec<usize>, Vec<f32>) = self.agents.iter().filter(|(_, a)| a.species == species )
+            .map(|(k, a)| (*k, a.fitness.max(0.)+1.) ).collect(); // probabilities
+
+        let mut rng = rand::rng();
+        let parent = keys[WeightedIndex::new(&fs).unwrap().sample(&mut rng)];
+        let child = self.offspring(&parent);
+        self.species_assign(&child);
+        child
+    }
+
+    /// Single step of rtNEAT replacement (see Replacement), regardless of interval.
+    /// Returns removed agent's key and it's replacement's, None if no agent is old enough
+    /// or there are less than two agents (nothing would be left to breed from).
+    pub fn replace(&mut self, min_age: usize) -> Option<(usize, usize)> {
+        // there has to be a parent left after removal
+        if self.agents.len() < 2 {return None}
+        let sizes = self.agents.values().counts_by(|a| a.species );
+        let adjusted = |a: &NN| a.fitness / sizes[&a.species] as f32;
+
+        let worst = *self.agents.iter().filter(|(_, a)| a.age >= min_age )
+            .min_by(|a, b| adjusted(a.1).total_cmp(&adjusted(b.1)) )?.0;
+        self.remove(&worst, Death::Replaced);
+
+        // average adjusted fitness, shifted so it's positive
+        let (species, fs): (Vec<usize>, Vec<f32>) = self.agents.values().into_group_map_by(|a| a.species ).into_iter()
+            .map(|(s, a)| (s, a.iter().map(|a| adjusted(a) ).sum::<f32>() / a.len() as f32) ).unzip();
+        let min = fs.iter().cloned().fold(f32::INFINITY, f32::min);
+        let fs: Vec<f32> = fs.iter().map(|f| f - min + 0.01 ).collect();
+
+        let mut rng = rand::rng();
+        let child = self.breed(species[WeightedIndex::new(&fs).unwrap().sample(&mut rng)]);
+        Some((worst, child))
+    }
+
+    /// Advances tick counter and applies lifecycle and replacement policies, run at the end of every forward.
+    /// Returns replacement made in this tick.
+    pub fn tick(&mut self) -> Option<(usize, usize)> {
+        self.ticks += 1;
+        self.lifecycle();
+        match self.replacement {
+            Some(r) if r.interval > 0 && self.ticks.is_multiple_of(r.interval) => self.replace(r.min_age),
+            _ => None,
+        }
+    }
+
+    /// Applies lifespan, cap and floor policies.
+    /// Spawned agents need input vectors from the next forward on (see events).
+    pub fn lifecycle(&mut self) {
+        if let Some(lifespan) = self.lifespan {
+            let old: Vec<usize> = self.agents.iter().filter(|(_, a)| a.age > lifespan ).map(|(k, _)| *k ).collect();
+            old.iter().for_each(|k| { self.remove(k, Death::Age); } );
+        }
+        if let Some(cap) = self.cap && self.agents.len() > cap {
+            let worst: Vec<usize> = self.agents.iter().sorted_by(|a, b| a.1.fitness.total_cmp(&b.1.fitness) )
+                .take(self.agents.len() - cap).map(|(k, _)| *k ).collect();
+            worst.iter().for_each(|k| { self.remove(k, Death::Cap); } );
+        }
+        if let Some(floor) = self.floor {
+            while self.agents.len() < floor && self.spawn().is_some() {}
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of mutation applied to network, in order of NN's chances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Mutation {
+    Weight,
+    ConnectionAdd,
+    NodeAdd,
+    GaterAdd,
+    GaterRemove,
+    ConnectionEnable,
+    ConnectionDisable,
+    NodeFunc,
+    /// Deletion of node or connection, when pruning is enabled.
+    Prune,
+}
+
+/**
+Genealogy of single network, ids are assigned by handler and never reused.
+In NeatContinous id is the same as agent's key, and birth is tick,
+in NeatIntermittent birth is generation.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lineage {
+    pub id: usize,
+    /// None for initial networks and ones inserted from outside.
+    pub parents: Option<(usize, usize)>,
+    pub birth: usize,
+    /// Mutations applied since birth.
+    pub mutations: Vec<Mutation>,
+}
+
+impl Lineage {
+    pub fn new(id: usize, parents: Option<(usize, usize)>, birth: usize) -> Self {
+        Self { id, parents, birth, mutations: vec![] }
+    }
+}
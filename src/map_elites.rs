@@ -0,0 +1,154 @@
+use std::{collections::HashMap, fs, path::Path};
+use itertools::Itertools;
+use rand::{seq::IteratorRandom, Rng};
+use rayon::prelude::*;
+
+use crate::{nn::innov_mutate, Lineage, NodeKey, NN};
+
+/**
+Struct for handling quality-diversity neuroevolution (MAP-Elites).
+Behaviour descriptor space is divided into grid, each cell keeps single best network (elite).
+Offspring are made from random elites, so archive is gradually filled with diverse solutions.
+There is no speciation, innovation numbers are shared like in other handlers.
+*/
+pub struct NeatMapElites {
+    /// Elites, key is cell's coordinates.
+    pub archive: HashMap<Vec<usize>, NN>,
+    /// Amount of cells in each descriptor dimension.
+    pub grid: Vec<usize>,
+    /// Descriptor range of each dimension, values outside are clamped.
+    pub bounds: Vec<(f32, f32)>,
+    /// Chance that offspring is crossover of two elites (otherwise it's mutated clone).
+    pub crossover_chance: f64,
+    /// Amount of mutations applied to each offspring.
+    pub mutations: usize,
+    /// First free innovation number.
+    pub innov_id: usize,
+    /// If connection have the same souce, destination, and recurrency, it has also same id.
+    pub innov_table: HashMap<(NodeKey, NodeKey, bool), usize>,
+    /// Incremented after each batch of offspring, used as their lineage birth.
+    pub generation: usize,
+    /// Lineage id of the next offspring, ids are never reused.
+    pub next_id: usize,
+    template: NN,
+}
+
+impl NeatMapElites {
+    /// Template is used for random networks, while archive is still empty.
+    /// Panics if grid and bounds lengths differ.
+    pub fn new(agent: &NN, grid: &[usize], bounds: &[(f32, f32)]) -> Self {
+        if grid.len() != bounds.len() {panic!("Grid and bounds dimensions don't match")}
+        Self {
+            archive: HashMap::new(),
+            grid: grid.to_vec(),
+            bounds: bounds.to_vec(),
+            crossover_chance: 0.25,
+            mutations: 1,
+            innov_id: agent.size.0+agent.size.1+1+agent.size_free.0+agent.size_free.1,
+            innov_table: HashMap::new(),
+            generation: 0,
+            next_id: 0,
+            template: agent.clone(),
+        }
+    }
+
+    /// Cell coordinates of behaviour descriptor.
+    pub fn cell(&self, descriptor: &[f32]) -> Vec<usize> {
+        if descriptor.len() != self.grid.len() {panic!("Descriptor dimension doesn't match grid")}
+        descriptor.iter().zip(self.grid.iter().zip(self.bounds.iter())).map(|(d, (g, (min, max)))| {
+            let x = ((d - min) / (max - min)).clamp(0., 1.);
+            ((x * *g as f32) as usize).min(g - 1)
+        }).collect()
+    }
+
+    /// Creates batch of networks to evaluate.
+    /// While archive is empty, they are random (template with few connections), 
+    /// later they are mutated clones or crossovers of random elites.
+    pub fn offspring(&mut self, amount: usize) -> Vec<NN> {
+        let mut rng = rand::rng();
+        let children = (0..amount).map(|_| {
+            let mut child = match self.archive.is_empty() {
+                true => {
+                    // same as in other handlers, each mutation results in new connection
+                    let mut a = self.template.clone();
+                    a.lineage = Lineage::new(self.next_id, None, self.generation);
+                    a.set_chances(&[0,1,0,0,0,0,0,0]);
+                    a.recurrence.0 = false;
+                    for _ in 0..=(a.size.0 + a.size.1)/2 { innov_mutate(&mut self.innov_table, &mut self.innov_id, &mut a); }
+                    a.set_chances(self.template.get_chances());
+                    a.recurrence = self.template.recurrence;
+                    a
+                }
+                false => {
+                    let a = self.archive.values().choose(&mut rng).unwrap();
+                    let (b, mut child) = match rng.random_bool(self.crossover_chance) {
+                        true => { let b = self.archive.values().choose(&mut rng).unwrap(); (b, a.crossover(b)) },
+                        false => (a, a.clone()),
+                    };
+                    child.lineage = Lineage::new(self.next_id, Some((a.lineage.id, b.lineage.id)), self.generation);
+                    for _ in 0..self.mutations { innov_mutate(&mut self.innov_table, &mut self.innov_id, &mut child); }
+                    child
+                }
+            };
+            self.next_id += 1;
+            child.active = true;
+            child.fitness = 0.;
+            child.age = 0;
+            child
+        }).collect();
+        self.generation += 1;
+        children
+    }
+
+    /// Places evaluated network in descriptor's cell, if it's empty or network is better than elite.
+    /// Returns true if network was accepted.
+    pub fn insert(&mut self, nn: &NN, descriptor: &[f32]) -> bool {
+        let cell = self.cell(descriptor);
+        match self.archive.get(&cell) {
+            Some(e) if e.fitness >= nn.fitness => false,
+            _ => { self.archive.insert(cell, nn.clone()); true }
+        }
+    }
+
+    /// Creates batch of offspring, evaluates them in parallel and inserts into archive.
+    /// Evaluation returns fitness and behaviour descriptor. Returns amount of accepted networks.
+    pub fn step<F>(&mut self, amount: usize, evaluate: F) -> usize 
+    where F: Fn(&mut NN) -> (f32, Vec<f32>) + Sync {
+        let results: Vec<(NN, Vec<f32>)> = self.offspring(amount).into_par_iter().map(|mut a| {
+            let (fitness, descriptor) = evaluate(&mut a);
+            a.fitness = fitness;
+            (a, descriptor)
+        }).collect();
+        results.into_iter().filter(|(a, d)| self.insert(a, d) ).count()
+    }
+
+    /// Filled fraction of all cells.
+    pub fn coverage(&self) -> f32 {
+        self.archive.len() as f32 / self.grid.iter().product::<usize>() as f32
+    }
+
+    /// Sum of elites fitness.
+    pub fn qd_score(&self) -> f32 {
+        self.archive.values().map(|a| a.fitness ).sum()
+    }
+
+    pub fn best(&self) -> Option<&NN> {
+        self.archive.values().max_by(|a, b| a.fitness.total_cmp(&b.fitness) )
+    }
+
+    /// Saves every elite into directory as `<cell coordinates>.toml`, 
+    /// along with "archive.csv" summary (cell, fitness, size) and "stats.csv" (coverage, qd-score).
+    pub fn save(&self, dir: &str) {
+        fs::create_dir_all(dir).unwrap();
+        let mut csv = "cell,fitness,nodes,connections\n".to_string();
+        self.archive.iter().sorted_by_key(|(c, _)| (*c).clone() ).for_each(|(c, a)| {
+            let name = c.iter().join("_");
+            a.save(Path::new(dir).join(name.clone() + ".toml").to_str().unwrap());
+            csv += &format!("{},{},{},{}\n", name, a.fitness, a.nodes.len(), a.connections.len());
+        });
+        fs::write(Path::new(dir).join("archive.csv"), csv).unwrap();
+        fs::write(Path::new(dir).join("stats.csv"), 
+            format!("cells,filled,coverage,qd_score\n{},{},{},{}\n", 
+                self.grid.iter().product::<usize>(), self.archive.len(), self.coverage(), self.qd_score())).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ use itertools::Itertools;
 use rand_distr::{weighted::WeightedIndex, Distribution};
 use rayon::prelude::*;
 
-use crate::{nn::NN, node::NodeKey, ActFunc, Connection};
+use crate::{hall_of_fame::HallOfFame, innovation::{self, InnovStats}, lifecycle::{Death, Event, Replacement}, lineage::Lineage, nn::{innov_get, innov_mutate, NN}, population::Population, node::NodeKey, novelty::Novelty, stats::{GenerationStats, Observer}, threshold::{ThresholdControl, ThresholdController}, ActFunc, Connection};
 
 // Single species data.
 pub struct Species {
@@ -12,12 +12,71 @@ pub struct Species {
     pub size: usize,
     pub offspring: usize,
     history_fitness: VecDeque<f32>,
+    // cached genome that new agents are compared against (continous mode only)
+    pub(crate) representative: Option<NN>,
 }
 impl Species {
     pub fn new(fitness: f32) -> Self {
-        Self { fitness, size: 0, offspring: 0, history_fitness: VecDeque::from_iter(std::iter::repeat(0.).take(20)) }
+        Self { fitness, size: 0, offspring: 0, history_fitness: VecDeque::from_iter(std::iter::repeat(0.).take(20)), representative: None }
+    }
+    pub fn fitness(&self) -> f32 {
+        self.fitness
     }
 }
+// each existing species takes the first of it's former members as leader, and every agent close enough to it,
+// leftovers found new species the same way, leaders are kept as representatives if requested
+fn speciate<'a>(agents: impl Iterator<Item = &'a mut NN>, species_table: &mut HashMap<usize, Species>, threshold: f32, representatives: bool) {
+    let mut refs = agents.collect_vec();
+    for s in species_table.iter_mut() {
+        let tbr;
+        match refs.iter().position(|a| a.species == *s.0 ) {
+            Some(p) => tbr = p,
+            None => {s.1.size = 0; continue;},
+        }
+        let f = refs.swap_remove(tbr);
+        s.1.size = 1;
+        if representatives { s.1.representative = Some(f.clone()); }
+
+        let mut assigned: Vec<usize> = vec![];
+        refs.iter_mut().enumerate().for_each(|(i,a)| {
+            let t = f.compare(a, 1., 1., 0.4, 1.);
+            if t < threshold { 
+                assigned.push(i);
+                a.species = f.species;
+                s.1.size += 1;
+            }
+        } );
+        // sorted and inverted so indexes doesn't change
+        assigned.iter().sorted().rev().for_each(|i| { refs.remove(*i); } );
+    }
+    species_table.retain(|_, s| s.size > 0 );
+    let mut uuid: usize = species_table.keys().max().unwrap_or(&0) + 1;
+
+    // creating new species for leftovers
+    while !refs.is_empty() {
+        let f = refs.swap_remove(0);
+        let mut species = Species::new(f.fitness);
+        f.species = uuid;
+        species.size = 1;
+        if representatives { species.representative = Some(f.clone()); }
+
+        // compare every leftover to the leader and assign if matches
+        let mut assigned: Vec<usize> = vec![];
+        refs.iter_mut().enumerate().for_each(|(i,a)| {
+            let t = f.compare(a, 1., 1., 0.4, 1.);
+            if t < threshold { 
+                assigned.push(i);
+                a.species = uuid;
+                species.size += 1;
+            }
+        } );
+        // sorted and inverted so indexes doesn't change
+        assigned.iter().sorted().rev().for_each(|i| { refs.remove(*i); } );
+        species_table.insert(uuid, species);
+        uuid += 1;
+    }
+}
+
 impl fmt::Debug for Species {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         let l = format!("[{}:{}, f:{:>.1}]", self.size, self.offspring, self.fitness);
@@ -41,21 +100,53 @@ pub struct NeatContinous {
     pub species_threshold: f32,
     /// Desired amout of species in ecosystem.
     pub species_amount: usize,
+    /// Corrects threshold towards species_amount, by default small steps (0.02) within 0.1..8.
+    pub threshold_controller: ThresholdController,
     /// HashMap of all non-empty species.
-    pub species_table: HashMap<usize, Species>
+    pub species_table: HashMap<usize, Species>,
+    /// If innovation table grows above it, unused entries are dropped (see innov_compact).
+    /// It should be well above amount of innovations carried by living agents.
+    pub innov_cap: Option<usize>,
+    /// Agents older than it (in forward calls) die.
+    pub lifespan: Option<usize>,
+    /// Below it agents are spawned from the best species.
+    pub floor: Option<usize>,
+    /// Above it the least fit agents die.
+    pub cap: Option<usize>,
+    /// rtNEAT periodic replacement of the worst agent, applied in tick.
+    pub replacement: Option<Replacement>,
+    /// Amount of forward calls.
+    pub ticks: usize,
+    /// Key of the next agent, keys are never reused.
+    pub next_key: usize,
+    /// Collected births and deaths, None if recording is disabled (see record_events).
+    pub(crate) events: Option<Vec<Event>>,
 } 
 impl NeatContinous {
     /// Each agent is a clone, but with it's own (random) initial genes.
     pub fn new(agent: &NN, size: usize, species_amount: usize) -> Self {
         let mut agents = HashMap::new();
-        (0..size).into_iter().for_each(|k| { agents.insert(k, agent.clone()); } );
+        (0..size).into_iter().for_each(|k| { 
+            let mut a = agent.clone();
+            a.lineage = Lineage::new(k, None, 0);
+            agents.insert(k, a);
+        } );
         let mut s = Self { 
             agents,
             innov_id: agent.size.0+agent.size.1+1+agent.size_free.0+agent.size_free.1, 
             innov_table: HashMap::new(),
             species_threshold: 3.,
             species_amount,
-            species_table: HashMap::new()
+            threshold_controller: ThresholdController::new(ThresholdControl::Step { step: 0.02, deadband: 1 }, f32::INFINITY, (0.1, 8.)),
+            species_table: HashMap::new(),
+            innov_cap: None,
+            lifespan: None,
+            floor: None,
+            cap: None,
+            replacement: None,
+            ticks: 0,
+            next_key: size,
+            events: None,
         };
 
         s.agents.par_iter_mut().for_each(|(_, a)|{ a.set_chances(&[0,1,0,0,0,0,0,0]); a.recurrence.0 = false; });
@@ -64,108 +155,164 @@ impl NeatContinous {
         s.agents.par_iter_mut().for_each(|(_, a)| { a.set_chances(agent.get_chances()); a.recurrence = agent.recurrence; });
         s
     }
-    /// Panics if any agent have no free space.
+    /// Adds input to every agent and stored genome, see Population::add_input.
     pub fn add_input(&mut self) {
-        if ! self.agents.values_mut().all(|a| a.add_input() ) {panic!("No more space for inputs")}
+        Population::add_input(self)
     }
-    /// Panics if any agent have no free space.
+    /// Adds output to every agent and stored genome, see Population::add_output.
     pub fn add_output(&mut self, func: &ActFunc) {
-        if ! self.agents.values_mut().all(|a| a.add_output(func) ) {panic!("No more space for outputs")}
+        Population::add_output(self, func)
+    }
+    /// Removes input (0 is the first one after bias) from every agent, see Population::remove_input.
+    pub fn remove_input(&mut self, index: usize) {
+        Population::remove_input(self, index)
+    }
+    /// Removes output from every agent, see Population::remove_output.
+    pub fn remove_output(&mut self, index: usize) {
+        Population::remove_output(self, index)
+    }
+    /// Reserves space for more inputs and outputs in every agent, see Population::grow_reserve.
+    pub fn grow_reserve(&mut self, inputs: usize, outputs: usize) {
+        Population::grow_reserve(self, inputs, outputs)
     }
     /// Creates new agent by crossing key's with other from the same species.
     /// Probably good idea to assign species to it right after.
     pub fn offspring(&mut self, key: &usize) -> usize {
         let agent_0 = self.agents.get(key).unwrap();
-        let child_key = self.agents.keys().max().unwrap() + 1;
 
         let (keys, fs): (Vec<usize>, Vec<f32>) = self.agents.iter().filter(|(_, a)| a.species == agent_0.species )
-            .map(|(k,a)| (k, a.fitness+1.) ).collect(); // probabilities
+            .map(|(k,a)| (*k, a.fitness+1.) ).collect(); // probabilities
             
         let mut rng = rand::rng();
 
         let dist = WeightedIndex::new(&fs).unwrap();
-        let agent_1 = self.agents.get( &keys[dist.sample(&mut rng)] ).unwrap();
+        let key_1 = keys[dist.sample(&mut rng)];
+        self.birth(key, &key_1)
+        //self.species_assign(&child_key); // assign to species 
+    }
+    /// Creates new agent by crossing two given ones (fitter parent's structure is inherited),
+    /// and assigns it to species, parents can be from different species.
+    pub fn offspring_with(&mut self, key_a: &usize, key_b: &usize) -> usize {
+        let child_key = self.birth(key_a, key_b);
+        self.species_assign(&child_key);
+        child_key
+    }
+    /// Creates new agent by crossing key's with mate chosen by user's closure, and assigns it to species.
+    /// Closure gets each candidate (key and network, other than key's own) and returns it's weight, 0 means it's not eligible.
+    /// Returns None if there are no eligible mates.
+    pub fn offspring_by<F: Fn(&usize, &NN) -> f32>(&mut self, key: &usize, mate: F) -> Option<usize> {
+        let (keys, fs): (Vec<usize>, Vec<f32>) = self.agents.iter().filter(|(k, _)| *k != key )
+            .map(|(k,a)| (*k, mate(k, a).max(0.)) ).filter(|(_, f)| *f > 0. ).unzip();
+        if keys.is_empty() {return None}
+
+        let mut rng = rand::rng();
+        let key_1 = keys[WeightedIndex::new(&fs).unwrap().sample(&mut rng)];
+        Some(self.offspring_with(key, &key_1))
+    }
+    // crossover, mutation and innovation numbers correction, without species assignment
+    fn birth(&mut self, key_0: &usize, key_1: &usize) -> usize {
+        let child_key = self.next_key;
+        self.next_key += 1;
+        let agent_0 = self.agents.get(key_0).unwrap();
+        let agent_1 = self.agents.get(key_1).unwrap();
         let mut child = agent_0.crossover(agent_1);
         child.active = true;
+        child.age = 0;
+        child.lineage = Lineage::new(child_key, Some((*key_0, *key_1)), self.ticks);
+        if let Some(s) = self.species_table.get_mut(&child.species) { s.size += 1; }
         self.agents.insert(child_key, child);
         self.mutate(&child_key);
-        //self.species_assign(&child_key); // assign to species 
-
+        self.event(Event::Birth { key: child_key, parents: Some((*key_0, *key_1)) });
         child_key
     }
+    /// Inserts network from other run (eg. loaded from file) and assigns it to species.
+    /// Innovation numbers are translated into this handler's ones.
+    /// Panics if I/O size doesn't match population (any size is accepted if it's empty).
+    pub fn insert_genome(&mut self, mut nn: NN) -> usize {
+        match self.agents.values().next() {
+            Some(a) if nn.size != a.size || nn.size_free != a.size_free => panic!("Genome I/O size doesn't match population"),
+            Some(_) => {}
+            // I/O keys of new size can't collide with innovation numbers
+            None => self.innov_id = self.innov_id.max(nn.size.0+nn.size.1+1+nn.size_free.0+nn.size_free.1),
+        }
+        let key = self.next_key;
+        self.next_key += 1;
+
+        nn.rekey(&mut self.innov_table, &mut self.innov_id);
+        nn.active = true;
+        nn.species = 0; // old id means nothing here
+        nn.age = 0;
+        nn.lineage = Lineage::new(key, None, self.ticks);
+        self.agents.insert(key, nn);
+        self.species_assign(&key);
+        self.event(Event::Birth { key, parents: None });
+        key
+    }
     /// Mutates agent and corrects innovation numbers (if needed).
     pub fn mutate(&mut self, key: &usize) {
-        let agent = &mut self.agents.get_mut(key).unwrap();
-        let ( n_conn, n_node) = agent.mutate();
-
-        if n_conn.is_some() {
-            let current = n_conn.unwrap();
-            if !self.innov_table.contains_key(&(current.from.clone(), current.to.clone(), current.recurrent)) {
-                self.innov_table.insert((current.from.clone(), current.to.clone(), current.recurrent), self.innov_id);
-                self.innov_id += 1;
-            }
-            let correct = self.innov_table.get(&(current.from.clone(), current.to.clone(), current.recurrent)).unwrap();
+        innov_mutate(&mut self.innov_table, &mut self.innov_id, self.agents.get_mut(key).unwrap());
+        if self.innov_cap.is_some_and(|c| self.innov_table.len() > c) { self.innov_compact(); }
+    }
 
-            assert!(agent.correct_keys(*correct, 0) == 1);
-        }
-        
-        if n_node.is_some() {
-            let current = n_node.unwrap();
-            if !self.innov_table.contains_key(&(current.0.from.clone(), current.0.to.clone(), current.0.recurrent)) {
-                self.innov_table.insert((current.0.from.clone(), current.0.to.clone(), current.0.recurrent), self.innov_id);
-                self.innov_id += 1;
-            }
-            if !self.innov_table.contains_key(&(current.1.from.clone(), current.1.to.clone(), current.1.recurrent)) {
-                self.innov_table.insert((current.1.from.clone(), current.1.to.clone(), current.1.recurrent), self.innov_id);
-                self.innov_id += 1;
-            }
-            let correct0 = self.innov_table.get(&(current.0.from.clone(), current.0.to.clone(), current.0.recurrent)).unwrap();
-            let correct1 = self.innov_table.get(&(current.1.from.clone(), current.1.to.clone(), current.1.recurrent)).unwrap();
+    /// Drops innovation table entries that no living agent carries, returns amount of removed ones.
+    /// Living agents' ids stay the same, removed ones are never reused.
+    /// Species representatives are counted as living.
+    pub fn innov_compact(&mut self) -> usize {
+        let representatives = self.species_table.values().filter_map(|s| s.representative.as_ref() );
+        innovation::compact(&mut self.innov_table, self.agents.values().chain(representatives))
+    }
 
-            assert!(agent.correct_keys(*correct0, *correct1) == 2);
-        }
+    pub fn innov_stats(&self) -> InnovStats {
+        let representatives = self.species_table.values().filter_map(|s| s.representative.as_ref() );
+        innovation::stats(&self.innov_table, self.innov_id, self.agents.values().chain(representatives))
     }
 
     // ********************************************************************************************
     /// Assigns agent to species according to threshold. 
+    /// Species sizes and representatives are kept up to date, so cost doesn't depend on population size.
     pub fn species_assign(&mut self, key: &usize) -> usize {
-        self.species_prune();
-        let mut species = None;
         let reference = self.agents.get(key).unwrap();
+        let old = reference.species;
 
-        // for loop needed bc continue/break doesn't work in for_each 
         // sorted from smallest species to promote them
-        for s in self.species_table.iter_mut().sorted_by_key(|(_,s)| s.size ) {
-            match self.agents.values().find(|a| a.species == *s.0 ) {
-                Some(a) => {
-                    if a.compare(reference, 1., 1., 0.4, 1.) < self.species_threshold {
-                        species = Some(a.species); break;
-                    } else {
-                        continue;
-                    }
-                }
-                None => continue,
+        let species = self.species_table.iter().sorted_by_key(|(k, s)| (s.size, **k) )
+            .find(|(_, s)| s.representative.as_ref()
+                .is_some_and(|r| r.compare(reference, 1., 1., 0.4, 1.) < self.species_threshold) )
+            .map(|(k, _)| *k );
+        let species = match species {
+            Some(s) => s,
+            None => {
+                let uuid = self.species_table.keys().max().unwrap_or(&0) + 1;
+                let mut s = Species::new(1.);
+                s.representative = Some(reference.clone());
+                self.species_table.insert(uuid, s);
+                uuid
             }
-        }
-        if species.is_none() {
-            let uuid = self.species_table.keys().max().unwrap_or(&0) + 1;
-            self.species_table.insert(uuid, Species::new(1.));
-            species = Some(uuid);
-        }
+        };
 
-        self.agents.get_mut(key).unwrap().species = species.unwrap();
+        if species != old {
+            self.species_leave(old);
+            self.species_table.get_mut(&species).unwrap().size += 1;
+        }
+        self.agents.get_mut(key).unwrap().species = species;
         self.species_threshold_correct();
-        species.unwrap()
+        species
+    }
+    // agent left species, empty ones are removed
+    pub(crate) fn species_leave(&mut self, species: usize) {
+        if let Some(s) = self.species_table.get_mut(&species) {
+            s.size = s.size.saturating_sub(1);
+            if s.size == 0 { self.species_table.remove(&species); }
+        }
     }
     /// Corrects threshold to hit target amout of species.
     /// Should be run after every offspring.
     pub fn species_threshold_correct(&mut self) { 
-        // no need for more intelligent approach bc of frequent usage
-        if self.species_amount > self.species_table.len() + 1 { self.species_threshold -= 0.02 }
-        else if self.species_amount < self.species_table.len() - 1 { self.species_threshold += 0.02 }
-        self.species_threshold = self.species_threshold.clamp(0.1, 8.);
+        self.species_threshold = self.threshold_controller.update(self.species_threshold, self.species_table.len(), self.species_amount);
     }
-    fn species_prune(&mut self) {
+    /// Recounts species sizes by scanning all agents, and removes empty ones.
+    /// Needed only if agents were inserted or removed by hand, instead of handler's methods.
+    pub fn species_recount(&mut self) {
         for s in &mut self.species_table {
             s.1.size = self.agents.iter().filter(|(_, a)| a.species == *s.0 ).count();
         }
@@ -174,53 +321,7 @@ impl NeatContinous {
     /// In continous type it's used only at init.
     /// Should be run several times, if you want to hit target amount of species.
     pub fn speciate(&mut self){
-        let mut refs = self.agents.iter_mut().map(|a| a).collect_vec();
-        for s in &mut self.species_table {
-            let tbr;
-            match refs.iter().position(|(_, a)| a.species == *s.0 ) {
-                Some(p) => tbr = p,
-                None => {s.1.size = 0; continue;},
-            }
-            let f = refs.swap_remove(tbr);
-            s.1.size = 1;
-
-            let mut assigned: Vec<usize> = vec![];
-            refs.iter_mut().enumerate().for_each(|(i,(_, a))| {
-                let t = f.1.compare(a, 1., 1., 0.4, 1.);
-                if t < self.species_threshold { 
-                    assigned.push(i);
-                    a.species = f.1.species;
-                    s.1.size += 1;
-                }
-            } );
-            // sorted and inverted so indexes doesn't change
-            assigned.iter().sorted().rev().for_each(|i| { refs.remove(*i); } );
-        }
-        self.species_table.retain(|_, s| s.size > 0 );
-        let mut uuid: usize = self.species_table.keys().max().unwrap_or(&0) + 1;
-
-        // creating new species for leftovers
-        while !refs.is_empty() {
-            let f = refs.swap_remove(0);
-            self.species_table.insert(uuid, Species::new(f.1.fitness));
-            f.1.species = uuid;
-            self.species_table.get_mut(&uuid).unwrap().size = 1;
-
-            // compare every leftover to the leader and assign if matches
-            let mut assigned: Vec<usize> = vec![];
-            refs.iter_mut().enumerate().for_each(|(i,(_, a))| {
-                let t = f.1.compare(a, 1., 1., 0.4, 1.);
-                if t < self.species_threshold { 
-                    assigned.push(i);
-                    a.species = uuid;
-                    self.species_table.get_mut(&uuid).unwrap().size += 1;
-                }
-            } );
-            // sorted and inverted so indexes doesn't change
-            assigned.iter().sorted().rev().for_each(|i| { refs.remove(*i); } );
-            
-            uuid += 1;
-        }
+        speciate(self.agents.values_mut(), &mut self.species_table, self.species_threshold, true);
         self.species_threshold_correct();
     }
     // ********************************************************************************************
@@ -235,14 +336,17 @@ impl NeatContinous {
                 None => empty.push(*k),
             }
         });
-        empty.iter().for_each(|k| {self.agents.remove(k);} );
+        empty.iter().for_each(|k| {self.remove(k, Death::Integrity);} );
     }
 
     /// Takes inputs for each agent, and runs it.
+    /// Afterwards agents get older, and lifecycle policies are applied (see tick).
     pub fn forward(&mut self, inputs: &HashMap<usize, Vec<f32>>) {
         self.agents.par_iter_mut().for_each(|(k,a)|{
             if a.active {a.process_network(inputs.get(k).unwrap());}
+            a.age += 1;
         });
+        self.tick();
     }
     /// Gets single network's output node's value's.
     pub fn get_outputs(&self, key: &usize) -> &Vec<f32>{
@@ -252,7 +356,12 @@ impl NeatContinous {
     /// Ratio of 0.1 means, that there is 10% chance of deleting node
     /// , and 90% of deleting connection.
     pub fn set_pruning(&mut self, enabled: bool, ratio: f32) {
-        self.agents.par_iter_mut().for_each(|(_,a)| a.set_pruning(enabled, ratio) );
+        Population::set_pruning(self, enabled, ratio)
+    }
+    /// If enabled each agent's chances and weight mutation's sigma (starting from given one) 
+    /// are mutated along with it, and inherited in crossover.
+    pub fn set_adaptive(&mut self, enabled: bool, sigma: f32) {
+        Population::set_adaptive(self, enabled, sigma)
     }
 }
 
@@ -274,26 +383,67 @@ pub struct NeatIntermittent {
     pub species_threshold: f32,
     /// Desired amout of species in ecosystem.
     pub species_amount: usize, 
+    /// Corrects threshold towards species_amount after speciate, by default proportionally (step up to 2).
+    pub threshold_controller: ThresholdController,
     /// HashMap of all non-empty species.
-    pub species_table: HashMap<usize, Species>
+    pub species_table: HashMap<usize, Species>,
+    /// If innovation table grows above it, unused entries are dropped (see innov_compact).
+    /// It should be well above amount of innovations carried by living agents.
+    pub innov_cap: Option<usize>,
+    /// Incremented after next_gen.
+    pub generation: usize,
+    /// Which agents already received fitness in ask/tell mode.
+    pub(crate) told: Vec<bool>,
+    /// Notified after speciate, next_gen and whole population mutation.
+    pub observers: Vec<Box<dyn Observer + Send>>,
+    /// Best networks ever seen, updated before agents are replaced in next_gen.
+    /// Disabled by default (zero capacity).
+    pub hall_of_fame: HallOfFame,
+    /// If enabled, selection uses blend of fitness and novelty (see novelty_evaluate).
+    pub novelty: Option<Novelty>,
+    /// If enabled, selection is based on NN::objectives, 
+    /// using non-dominated sorting and crowding distance (NSGA-II).
+    pub multi_objective: bool,
+    /// Lineage id of the next agent, ids are never reused.
+    pub next_id: usize,
 } 
 
 impl NeatIntermittent {
-    // there need to be minimal (>0) amount of connections at the start
-    // but it needs to be done through mutate function, so innovation numbers are kept
-    // so for mutation procedure the chances are modified as so each mutation results in new conn
-    /// Each agent is a clone, but with it’s own (random) initial genes.
-    pub fn new(agent: &NN, size: usize, species_amount: usize) -> Self {
-        let agents = (0..size).into_iter().map(|_| agent.clone() ).collect();
-        let mut s = Self { 
-            agents,
+    // handler without agents, template is used only for I/O sizes
+    fn blank(agent: &NN, size: usize, species_amount: usize) -> Self {
+        Self { 
+            agents: vec![],
             size,
             innov_id: agent.size.0+agent.size.1+1+agent.size_free.0+agent.size_free.1, 
             innov_table: HashMap::new(),
             species_threshold: 3.,
             species_amount,
-            species_table: HashMap::new()
-        };
+            threshold_controller: ThresholdController::new(ThresholdControl::Proportional { kp: 1. }, 2., (f32::NEG_INFINITY, f32::INFINITY)),
+            species_table: HashMap::new(),
+            innov_cap: None,
+            generation: 0,
+            told: vec![],
+            observers: vec![],
+            hall_of_fame: HallOfFame::default(),
+            novelty: None,
+            multi_objective: false,
+            next_id: 0,
+        }
+    }
+
+    // there need to be minimal (>0) amount of connections at the start
+    // but it needs to be done through mutate function, so innovation numbers are kept
+    // so for mutation procedure the chances are modified as so each mutation results in new conn
+    /// Each agent is a clone, but with it’s own (random) initial genes.
+    pub fn new(agent: &NN, size: usize, species_amount: usize) -> Self {
+        let agents = (0..size).into_iter().map(|i| {
+            let mut a = agent.clone();
+            a.lineage = Lineage::new(i, None, 0);
+            a
+        }).collect();
+        let mut s = Self::blank(agent, size, species_amount);
+        s.agents = agents;
+        s.next_id = size;
 
         s.agents.par_iter_mut().for_each(|a|{ a.set_chances(&[0,1,0,0,0,0,0,0]); });
         for _ in 0..=(agent.size.0 + agent.size.1)/2 {s.mutate(None);}
@@ -301,13 +451,62 @@ impl NeatIntermittent {
         s
     }
 
-    /// Panics if any agent have no free space.
+    /// Population is seeded from saved champions (eg. best of previous run) instead of blank network.
+    /// Champions are inserted unchanged, rest of agents are their clones with weights jittered
+    /// (normal distribution with "jitter" sigma), and "mutations" amount of structural mutations.
+    /// Panics if there are no champions, or they have different I/O sizes.
+    pub fn from_genomes(genomes: &[NN], size: usize, species_amount: usize, jitter: f32, mutations: usize) -> Self {
+        let agent = genomes.first().expect("At least one genome is needed");
+        if genomes.iter().any(|g| g.size != agent.size || g.size_free != agent.size_free ) {panic!("Genomes I/O sizes doesn't match")}
+        let mut s = Self::blank(agent, size, species_amount);
+
+        let champions: Vec<NN> = genomes.iter().map(|g| {
+            let mut g = g.clone();
+            g.rekey(&mut s.innov_table, &mut s.innov_id);
+            g.fitness = 0.;
+            g.species = 0;
+            g.active = true;
+            g
+        }).collect();
+        s.agents = champions.iter().cycle().take(size.max(champions.len())).cloned().collect();
+        s.agents.iter_mut().for_each(|a| { a.lineage = Lineage::new(s.next_id, None, 0); s.next_id += 1; });
+
+        // weight chance is zeroed, so each mutation changes structure
+        s.agents.iter_mut().skip(champions.len()).for_each(|a| {
+            a.jitter_weights(jitter);
+            let mut chances = *a.get_chances();
+            chances[0] = 0;
+            a.set_chances(&chances);
+        });
+        for i in champions.len()..s.agents.len() {
+            if s.agents[i].get_chances().iter().sum::<usize>() > 0 {
+                for _ in 0..mutations {s.mutate(Some(i));}
+            }
+            let chances = *champions[i % champions.len()].get_chances();
+            s.agents[i].set_chances(&chances);
+        }
+        s
+    }
+
+    /// Adds input to every agent and stored genome, see Population::add_input.
     pub fn add_input(&mut self) {
-        if ! self.agents.iter_mut().all(|a| a.add_input() ) {panic!("No more space for inputs")}
+        Population::add_input(self)
     }
-    /// Panics if any agent have no free space.
+    /// Adds output to every agent and stored genome, see Population::add_output.
     pub fn add_output(&mut self, func: &ActFunc) {
-        if ! self.agents.iter_mut().all(|a| a.add_output(func) ) {panic!("No more space for outputs")}
+        Population::add_output(self, func)
+    }
+    /// Removes input (0 is the first one after bias) from every agent, see Population::remove_input.
+    pub fn remove_input(&mut self, index: usize) {
+        Population::remove_input(self, index)
+    }
+    /// Removes output from every agent, see Population::remove_output.
+    pub fn remove_output(&mut self, index: usize) {
+        Population::remove_output(self, index)
+    }
+    /// Reserves space for more inputs and outputs in every agent, see Population::grow_reserve.
+    pub fn grow_reserve(&mut self, inputs: usize, outputs: usize) {
+        Population::grow_reserve(self, inputs, outputs)
     }
     // connections are the same only if they have same addresses AND appeared in the same gen 
     // another option is to use 2d global connection lookup table that is filled with innov id's 
@@ -316,126 +515,116 @@ impl NeatIntermittent {
     /// Mutates agent and corrects innovation numbers (if needed).
     /// If "single" is provided, only agent with that index is mutated.
     pub fn mutate(&mut self, single: Option<usize>) {
-        let mut all_n_conn: Vec<(usize, Connection)> = vec![]; // NN idx, added conn (from, to)
-        let mut all_n_node: Vec<(usize, ( Connection, Connection ))> = vec![]; // NN idx, splitted conn innovation number
-
         match single {
-            Some(i) => {
-                let ( n_conn, n_node) = self.agents[i].mutate();
-                match n_conn {
-                    None => {},
-                    Some(c) => {all_n_conn.push((i, c));},
-                }
-                match n_node {
-                    None => {},
-                    Some(n) => {all_n_node.push((i, n));},
-                }
-            }
+            Some(i) => innov_mutate(&mut self.innov_table, &mut self.innov_id, &mut self.agents[i]),
             None => {
-                let results: Vec<(usize, (Option<Connection>, Option<(Connection, Connection)>))> = self.agents
-                .par_iter_mut().enumerate()
-                .map(|(i, a)| (i, a.mutate()) ).collect();
-                
-                let conn_to_extend: Vec<_> = results.par_iter().filter_map(|(i, (n_conn, _))| {
-                    n_conn.as_ref().map(|conn_ref| (*i, conn_ref.clone()))
-                }).collect();
-                all_n_conn.extend(conn_to_extend);
-                
-                let node_to_extend: Vec<_> = results.par_iter().filter_map(|(i, (_, n_node))| {
-                    n_node.as_ref().map(|node_ref| (*i, node_ref.clone()))
-                }).collect();
-                all_n_node.extend(node_to_extend);
+                // agents mutate in parallel, innovation numbers are registered afterwards in order
+                let results: Vec<(Option<Connection>, Option<(Connection, Connection)>)> = self.agents
+                    .par_iter_mut().map(|a| a.mutate() ).collect();
+                results.iter().enumerate().for_each(|(i, (n_conn, _))| if let Some(c) = n_conn {
+                    let correct = innov_get(&mut self.innov_table, &mut self.innov_id, (c.from.clone(), c.to.clone(), c.recurrent));
+                    assert!(self.agents[i].correct_keys(correct, 0) == 1);
+                });
+                results.iter().enumerate().for_each(|(i, (_, n_node))| if let Some((c0, c1)) = n_node {
+                    let correct0 = innov_get(&mut self.innov_table, &mut self.innov_id, (c0.from.clone(), c0.to.clone(), c0.recurrent));
+                    let correct1 = innov_get(&mut self.innov_table, &mut self.innov_id, (c1.from.clone(), c1.to.clone(), c1.recurrent));
+                    assert!(self.agents[i].correct_keys(correct0, correct1) == 2);
+                });
             }
         }
-        // clones first element and extracts every other that looks like the same mutation
-        // repeats until all elements are extracted
-        all_n_conn.iter().for_each(|current| {
-            if !self.innov_table.contains_key(&(current.1.from.clone(), current.1.to.clone(), current.1.recurrent)) {
-                self.innov_table.insert((current.1.from.clone(), current.1.to.clone(), current.1.recurrent), self.innov_id);
-                self.innov_id += 1;
-            }
-            let correct = self.innov_table.get(&(current.1.from.clone(), current.1.to.clone(), current.1.recurrent)).unwrap();
+        if self.innov_cap.is_some_and(|c| self.innov_table.len() > c) { self.innov_compact(); }
+        if single.is_none() { self.notify(|o, s| o.mutated(s)); }
+    }
 
-            assert!(self.agents[current.0].correct_keys(*correct, 0) == 1);
-        });
-        
-        all_n_node.iter().for_each(|current| {
-            if !self.innov_table.contains_key(&(current.1.0.from.clone(), current.1.0.to.clone(), current.1.0.recurrent)) {
-                self.innov_table.insert((current.1.0.from.clone(), current.1.0.to.clone(), current.1.0.recurrent), self.innov_id);
-                self.innov_id += 1;
+    /// Drops innovation table entries that no living agent carries, returns amount of removed ones.
+    /// Living agents' ids stay the same, removed ones are never reused.
+    /// Hall of fame networks are counted as living.
+    pub fn innov_compact(&mut self) -> usize {
+        innovation::compact(&mut self.innov_table, self.agents.iter().chain(self.hall_of_fame.genomes.iter()))
+    }
+
+    pub fn innov_stats(&self) -> InnovStats {
+        innovation::stats(&self.innov_table, self.innov_id, self.agents.iter().chain(self.hall_of_fame.genomes.iter()))
+    }
+
+    /// Inserts network from other run (eg. loaded from file) and assigns it to species, returns it's index.
+    /// Innovation numbers are translated into this handler's ones.
+    /// Panics if I/O size doesn't match population (any size is accepted if it's empty).
+    pub fn insert_genome(&mut self, mut nn: NN) -> usize {
+        match self.agents.first() {
+            Some(a) if nn.size != a.size || nn.size_free != a.size_free => panic!("Genome I/O size doesn't match population"),
+            Some(_) => {}
+            // I/O keys of new size can't collide with innovation numbers
+            None => self.innov_id = self.innov_id.max(nn.size.0+nn.size.1+1+nn.size_free.0+nn.size_free.1),
+        }
+
+        nn.rekey(&mut self.innov_table, &mut self.innov_id);
+        nn.active = true;
+        nn.species = 0; // old id means nothing here
+        nn.lineage = Lineage::new(self.next_id, None, self.generation);
+        self.next_id += 1;
+        self.agents.push(nn);
+        self.species_assign(self.agents.len() - 1);
+        self.agents.len() - 1
+    }
+
+    /// Inserts "amount" of best hall of fame networks back into population, returns their indexes.
+    /// Panics if population I/O size changed since they were archived.
+    pub fn reintroduce(&mut self, amount: usize) -> Vec<usize> {
+        let genomes: Vec<NN> = self.hall_of_fame.genomes.iter().take(amount).cloned().collect();
+        genomes.into_iter().map(|g| self.insert_genome(g) ).collect()
+    }
+
+    /// Assigns single agent to existing species according to threshold, or creates new one.
+    /// Agent leaves it's previous species (which is removed if it's empty). Threshold isn't corrected.
+    pub fn species_assign(&mut self, idx: usize) -> usize {
+        let reference = &self.agents[idx];
+        let old = reference.species;
+        // first other member represents species
+        let species = self.species_table.keys().sorted()
+            .find(|s| match self.agents.iter().enumerate().find(|(i, a)| *i != idx && a.species == **s ) {
+                Some((_, a)) => a.compare(reference, 1., 1., 0.4, 1.) < self.species_threshold,
+                None => false,
+            }).cloned();
+        let species = match species {
+            Some(s) => s,
+            None if self.species_table.get(&old).is_some_and(|s| s.size == 1 ) => old, // alone in it's own species
+            None => {
+                let uuid = self.species_table.keys().max().unwrap_or(&0) + 1;
+                self.species_table.insert(uuid, Species::new(reference.fitness));
+                uuid
             }
-            if !self.innov_table.contains_key(&(current.1.1.from.clone(), current.1.1.to.clone(), current.1.1.recurrent)) {
-                self.innov_table.insert((current.1.1.from.clone(), current.1.1.to.clone(), current.1.1.recurrent), self.innov_id);
-                self.innov_id += 1;
+        };
+        if species != old {
+            if let Some(s) = self.species_table.get_mut(&old) {
+                s.size = s.size.saturating_sub(1);
+                if s.size == 0 { self.species_table.remove(&old); }
             }
-            let correct0 = self.innov_table.get(&(current.1.0.from.clone(), current.1.0.to.clone(), current.1.0.recurrent)).unwrap();
-            let correct1 = self.innov_table.get(&(current.1.1.from.clone(), current.1.1.to.clone(), current.1.1.recurrent)).unwrap();
-
-            assert!(self.agents[current.0].correct_keys(*correct0, *correct1) == 2);
-        });
+            self.species_table.get_mut(&species).unwrap().size += 1;
+        }
+        self.agents[idx].species = species;
+        species
     }
 
     /// Assigns all agents to species, and corrects threshold.
     /// At init should be run few times.
     pub fn speciate(&mut self){
-        let mut refs = self.agents.iter_mut().map(|a| a).collect_vec();
-        for s in &mut self.species_table {
-            let tbr;
-            match refs.iter().position(|a| a.species == *s.0 ) {
-                Some(p) => tbr = p,
-                None => {s.1.size = 0; continue;},
-            }
-            let f = refs.swap_remove(tbr);
-            s.1.size = 1;
-
-            let mut assigned: Vec<usize> = vec![];
-            refs.iter_mut().enumerate().for_each(|(i,a)| {
-                let t = f.compare(a, 1., 1., 0.4, 1.);
-                if t < self.species_threshold { 
-                    assigned.push(i);
-                    a.species = f.species;
-                    s.1.size += 1;
-                }
-            } );
-            // sorted and inverted so indexes doesn't change
-            assigned.iter().sorted().rev().for_each(|i| { refs.remove(*i); } );
-        }
-        self.species_table.retain(|_, s| s.size > 0 );
-        let mut uuid: usize = self.species_table.keys().max().unwrap_or(&0) + 1;
-
-        // creating new species for leftovers
-        while !refs.is_empty() {
-            let f = refs.swap_remove(0);
-            self.species_table.insert(uuid, Species::new(f.fitness));
-            f.species = uuid;
-            self.species_table.get_mut(&uuid).unwrap().size = 1;
-
-            // compare every leftover to the leader and assign if matches
-            let mut assigned: Vec<usize> = vec![];
-            refs.iter_mut().enumerate().for_each(|(i,a)| {
-                let t = f.compare(a, 1., 1., 0.4, 1.);
-                if t < self.species_threshold { 
-                    assigned.push(i);
-                    a.species = uuid;
-                    self.species_table.get_mut(&uuid).unwrap().size += 1;
-                }
-            } );
-            // sorted and inverted so indexes doesn't change
-            assigned.iter().sorted().rev().for_each(|i| { refs.remove(*i); } );
-            
-            uuid += 1;
-        }
-        let diff = 1. - (self.species_table.len() as f32) / (self.species_amount as f32);
-        self.species_threshold -= diff.clamp(-2., 2.);
+        speciate(self.agents.iter_mut(), &mut self.species_table, self.species_threshold, false);
+        self.species_threshold = self.threshold_controller.update(self.species_threshold, self.species_table.len(), self.species_amount);
+        self.notify(|o, s| o.speciated(s));
     }
 
     /// Creates new agent's generation. Each species have offspring size based on it's size and avg fitness.
     /// Inside single species, higher fitness means more chance to become parent.
+    /// With novelty search or multi-objective mode enabled, fitness is replaced by score (see scores).
     pub fn next_gen(&mut self) {
+        self.generation += 1;
+        self.hall_of_fame.update(&self.agents);
 
+        let scores = self.scores();
         // species fitness 
-        self.agents.iter().for_each(|a| 
-            self.species_table.get_mut(&a.species).unwrap().fitness += a.fitness / self.species_table.get(&a.species).unwrap().size as f32
+        self.agents.iter().zip(scores.iter()).for_each(|(a, f)| 
+            self.species_table.get_mut(&a.species).unwrap().fitness += f / self.species_table.get(&a.species).unwrap().size as f32
         );
         self.species_table.par_iter_mut().for_each(|(_, s)| {
             s.fitness /= s.size as f32;
@@ -453,7 +642,7 @@ impl NeatIntermittent {
 
         self.agents = self.species_table.par_iter().flat_map(|(uuid, species)|{
             let (idxs, fs): (Vec<usize>, Vec<f32>) = self.agents.iter().enumerate().filter(|(_, a)| a.species == *uuid )
-                .map(|(ai,a)| (ai, a.fitness+1.) ).collect(); // probabilities
+                .map(|(ai,_)| (ai, scores[ai]+1.) ).collect(); // probabilities
             
             let mut rng = rand::rng();
             let mut agents: Vec<NN> = vec![];
@@ -462,12 +651,30 @@ impl NeatIntermittent {
                 let dist = WeightedIndex::new(&fs).unwrap();
                 let idx0 = idxs[dist.sample(&mut rng)];  // index of chosen parent
                 let idx1 = idxs[dist.sample(&mut rng)];  // index of chosen parent
-                let mut child = self.agents.get(idx0).unwrap().crossover(self.agents.get(idx1).unwrap());
+                let (a0, a1) = (&self.agents[idx0], &self.agents[idx1]);
+                let mut child = a0.crossover(a1);
                 child.active = true;
+                child.lineage = Lineage::new(0, Some((a0.lineage.id, a1.lineage.id)), self.generation);
                 agents.push(child);
             });
             agents
         }).collect::<Vec<NN>>();
+        // ids are given after parallel part, so they stay unique
+        self.agents.iter_mut().for_each(|a| { a.lineage.id = self.next_id; self.next_id += 1; });
+        self.notify(|o, s| o.reproduced(s));
+    }
+
+    /// Adds observer, that will receive population statistics.
+    pub fn observe(&mut self, observer: impl Observer + Send + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    fn notify(&mut self, f: fn(&mut dyn Observer, &GenerationStats)) {
+        if self.observers.is_empty() || self.agents.is_empty() {return}
+        let mut observers = std::mem::take(&mut self.observers);
+        let stats = GenerationStats::collect(self);
+        observers.iter_mut().for_each(|o| f(o.as_mut(), &stats) );
+        self.observers = observers;
     }
 
     /// Forwards inputs to all agents
@@ -484,7 +691,12 @@ impl NeatIntermittent {
     /// If enabled each mutation deletes node or connection. 
     /// Ratio of 0.1 means, that there is 10% chance of deleting node , and 90% of deleting connection.
     pub fn set_pruning(&mut self, enabled: bool, ratio: f32) {
-        self.agents.par_iter_mut().for_each(|a| a.set_pruning(enabled, ratio) );
+        Population::set_pruning(self, enabled, ratio)
+    }
+    /// If enabled each agent's chances and weight mutation's sigma (starting from given one) 
+    /// are mutated along with it, and inherited in crossover.
+    pub fn set_adaptive(&mut self, enabled: bool, sigma: f32) {
+        Population::set_adaptive(self, enabled, sigma)
     }
 }
 
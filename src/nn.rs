@@ -6,7 +6,7 @@ use serde_with::serde_as;
 use core::f32;
 use std::{collections::{HashMap, HashSet}, fmt, fs::File, io::{Read, Write}};
 
-use crate::{connection::Connection, node::{ActFunc, Genre, Node, NodeKey}};
+use crate::{connection::Connection, lineage::{Lineage, Mutation}, node::{ActFunc, Genre, Node, NodeKey}};
 
 /// NEAT network representation.
 #[serde_as]
@@ -32,6 +32,9 @@ pub struct NN {
 
     chances: [usize; 8], // chances for mutations to happen, sum does NOT need to be equal 100
     pruning: (bool, f32),
+    // self-adaptation: chances and weight mutation's sigma are mutated and inherited too
+    #[serde(default)]
+    adaptive: (bool, f32),
     /// Are recurrent connections allowed, and
     /// how much new data influences node's memory.
     /// Useful if you want more stable memory value.
@@ -43,8 +46,20 @@ pub struct NN {
     pub functions_allowed: Vec<ActFunc>,
     /// Higher fitness gives higher chanceto become parent during crossover.
     pub fitness: f32,
+    /// Used instead of fitness in multi-objective mode, higher is better.
+    #[serde(default)]
+    pub objectives: Vec<f32>,
+    /// Sparseness of behaviour, used by novelty search.
+    #[serde(default)]
+    pub novelty: f32,
     /// Species ID.
     pub species: usize,
+    /// Incremented by NeatContinous::forward.
+    #[serde(default)]
+    pub age: usize,
+    /// Id, parents and mutations, set by handlers.
+    #[serde(default)]
+    pub lineage: Lineage,
     pub active: bool,
 }
 
@@ -80,11 +95,16 @@ impl NN {
             outputs: vec![0.; output_count],
             chances: [200, 20, 5, 10, 3, 0, 0, 0], // weight, ca, na, ga, gr, cn, cf, am
             pruning: (false, 0.33),
+            adaptive: (false, 1.),
             recurrence: (recurrence, new_data_weight_rec),
             function_io,
             functions_allowed: functions_allowed.to_vec(),
             fitness: 0.,
+            objectives: vec![],
+            novelty: 0.,
             species: 0,
+            age: 0,
+            lineage: Lineage::default(),
             active: true,
         };
         s.sort_layers();
@@ -145,8 +165,9 @@ impl NN {
         let mut g_d = 0;    // disjoint 
         let mut g_wd = 0.;  // sum of weight differences 
         let mut g_m = 0;    // amount of matching genes
-        let max_a = self.connections.keys().max().unwrap();
-        let max_b = nn.connections.keys().max().unwrap();
+        // genome can be left without connections after input removal
+        let max_a = self.connections.keys().max().unwrap_or(&0);
+        let max_b = nn.connections.keys().max().unwrap_or(&0);
         let max = max_a.max(max_b);
         for i in 0 ..= *max {
             let a = match self.connections.get(&i)  { Some(c) => c.weight, None => f32::MAX };
@@ -156,7 +177,7 @@ impl NN {
             else if *max_a.min(max_b) < i && (a != f32::MAX || b != f32::MAX) { g_e += 1;}
             else if a != f32::MAX || b != f32::MAX { g_d += 1; }
         }
-        let amount = self.connections.len().max(nn.connections.len());
+        let amount = self.connections.len().max(nn.connections.len()).max(1);
         let excess =    ( c1 * (g_e as f32) ) / ( amount as f32 );
         let disjoint =  ( c2 * (g_d as f32) ) / ( amount as f32 );
         let weight = if g_m != 0  {c3 * ((g_wd as f32) / (g_m as f32) )} else {0.};
@@ -184,8 +205,8 @@ impl NN {
             true => self.clone(),
             false => nn.clone()
         };
-        let max_a = self.connections.keys().max().unwrap();
-        let max_b = nn.connections.keys().max().unwrap();
+        let max_a = self.connections.keys().max().unwrap_or(&0);
+        let max_b = nn.connections.keys().max().unwrap_or(&0);
         for i in 0 ..= *max_a.max(max_b) {
             let a = self.connections.get(&i);
             let b = nn.connections.get(&i);
@@ -197,6 +218,12 @@ impl NN {
                 // in node's source, so from fittest parent
             }
         }
+        // self-adaptive rates are averaged too
+        if self.adaptive.0 && nn.adaptive.0 {
+            child.adaptive.1 = (self.adaptive.1 + nn.adaptive.1) / 2.;
+            child.chances.iter_mut().zip(self.chances.iter().zip(nn.chances.iter()))
+                .for_each(|(c, (a, b))| *c = (*a + *b).div_ceil(2) );
+        }
         child
     }
 
@@ -272,22 +299,26 @@ impl NN {
         let mut n_conn = None;
         let mut n_node = None;
 
+        if self.adaptive.0 { self.adapt(); }
+
         if ! self.pruning.0 {
             let dist = WeightedIndex::new(&self.chances).unwrap();
-            match dist.sample(&mut rng) {
-                0 => self.m_connection_weight(),
-                1 => {n_conn = self.m_connection_add();},
-                2 => {n_node = self.m_node_add();},
-                3 => self.m_connection_gater_add(),
-                4 => self.m_connection_gater_remove(),
-                5 => self.m_connection_enable(),
-                6 => self.m_connection_disable(),
-                7 => self.m_node_func(),
+            let m = match dist.sample(&mut rng) {
+                0 => {self.m_connection_weight(); Mutation::Weight},
+                1 => {n_conn = self.m_connection_add(); Mutation::ConnectionAdd},
+                2 => {n_node = self.m_node_add(); Mutation::NodeAdd},
+                3 => {self.m_connection_gater_add(); Mutation::GaterAdd},
+                4 => {self.m_connection_gater_remove(); Mutation::GaterRemove},
+                5 => {self.m_connection_enable(); Mutation::ConnectionEnable},
+                6 => {self.m_connection_disable(); Mutation::ConnectionDisable},
+                7 => {self.m_node_func(); Mutation::NodeFunc},
                 _ => unreachable!(),
-            }
+            };
+            self.lineage.mutations.push(m);
         }
         else {
             n_conn = self.prune(self.pruning.1 as f64);
+            self.lineage.mutations.push(Mutation::Prune);
         }
 
         self.sort_layers();
@@ -295,6 +326,18 @@ impl NN {
         (n_conn, n_node)
     }
 
+    /// Log-normal perturbation of chances and weight sigma.
+    /// Disabled mutations (zero chance) stay disabled, enabled ones can't drop to zero.
+    fn adapt(&mut self) {
+        const TAU: f32 = 0.2;
+        let mut rng = rand::rng();
+        let dist = Normal::new(0., TAU).unwrap();
+        self.chances.iter_mut().filter(|c| **c > 0 ).for_each(|c| {
+            *c = ((*c as f32 * dist.sample(&mut rng).exp()).round() as usize).clamp(1, 10000);
+        });
+        self.adaptive.1 = (self.adaptive.1 * dist.sample(&mut rng).exp()).clamp(0.01, 5.);
+    }
+
     /// Deletes nodes and connections
     fn prune(&mut self, p: f64) -> Option<Connection>  {
         let mut rng = rand::rng();
@@ -459,10 +502,83 @@ impl NN {
             counter += 1;
         }
         counter
-    } 
+    }
+
+    // renames nodes and connections, those touching removed nodes are dropped
+    pub(crate) fn relocate(&mut self, node: &impl Fn(&NodeKey) -> Option<NodeKey>, ids: &HashMap<usize, usize>,
+        size: (usize, usize), size_free: (usize, usize)
+    ) {
+        self.connections = self.connections.drain().filter_map(|(id, mut c)| {
+            c.from = node(&c.from)?;
+            c.to = node(&c.to)?;
+            c.gater = c.gater.and_then(|g| node(&g) );
+            Some((*ids.get(&id).unwrap_or(&id), c))
+        }).collect();
+        self.nodes = self.nodes.drain().filter_map(|(k, n)| Some((node(&k)?, n)) ).collect();
+        self.size = size;
+        self.size_free = size_free;
+        self.outputs = vec![0.; size.1];
+        self.sort_layers();
+        self.free_nodes_calc();
+    }
+
+    /// Translates network's innovation numbers into ones used by given table,
+    /// so network from other run (eg. loaded from file) can join ongoing neat.
+    /// Connections are matched by (from, to, recurrent), missing entries are allocated from innov_id.
+    /// Hidden nodes are re-keyed after connection they split, if it's gone they get fresh number.
+    pub fn rekey(&mut self, innov_table: &mut HashMap<(NodeKey, NodeKey, bool), usize>, innov_id: &mut usize) {
+        // I/O keys depend only on size, so they stay the same
+        let mut map: HashMap<NodeKey, NodeKey> = self.nodes.iter().filter(|(_,n)| n.genre != Genre::Hidden )
+            .map(|(k,_)| (k.clone(), k.clone()) ).collect();
+        let mut pending: Vec<NodeKey> = self.nodes.iter().filter(|(_,n)| n.genre == Genre::Hidden )
+            .map(|(k,_)| k.clone() ).sorted().collect();
+
+        // node can be translated only after both ends of it's split connection
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|k| match self.connections.get(&k.sconn) {
+                Some(c) => map.contains_key(&c.from) && map.contains_key(&c.to),
+                None => false,
+            });
+            let old = match ready {
+                Some(p) => pending.remove(p),
+                None => pending.remove(0), // split connection is missing or cycled
+            };
+            let sconn = match (ready, self.connections.get(&old.sconn)) {
+                (Some(_), Some(c)) => innov_get(innov_table, innov_id,
+                    (map.get(&c.from).unwrap().clone(), map.get(&c.to).unwrap().clone(), c.recurrent)),
+                _ => { *innov_id += 1; *innov_id - 1 },
+            };
+            let mut key = NodeKey::new(sconn, old.dup);
+            while map.values().contains(&key) { key.dup += 1; }
+            map.insert(old, key);
+        }
+
+        let mut connections = HashMap::new();
+        self.connections.values().sorted_by_key(|c| (c.from.clone(), c.to.clone(), c.recurrent) ).for_each(|c| {
+            let mut c = c.clone();
+            c.from = map.get(&c.from).unwrap().clone();
+            c.to = map.get(&c.to).unwrap().clone();
+            c.gater = c.gater.map(|g| map.get(&g).unwrap().clone() );
+            let id = innov_get(innov_table, innov_id, (c.from.clone(), c.to.clone(), c.recurrent));
+            if connections.insert(id, c).is_some() {panic!("Conn insert failed")}
+        });
+        self.connections = connections;
+        self.nodes = self.nodes.drain().map(|(k, n)| (map.get(&k).unwrap().clone(), n) ).collect();
+
+        self.sort_layers();
+        self.free_nodes_calc();
+    }
 
 // #########################################################################################################################################
 
+    /// Shifts every connection's weight by value from normal distribution.
+    pub fn jitter_weights(&mut self, sigma: f32) {
+        if sigma <= 0. {return}
+        let mut rng = rand::rng();
+        let dist = Normal::new(0., sigma).unwrap();
+        self.connections.values_mut().for_each(|c| c.assign_weight(c.weight + dist.sample(&mut rng)) );
+    }
+
     fn m_connection_weight(&mut self){
         let mut rng = rand::rng();
         // small chance for new value, otherwise slight change from normal distribution
@@ -471,7 +587,9 @@ impl NN {
             Some((_,c)) => {
                 match rng.random_range(0..10) {
                     0 => c.weight = rng.random_range(-5.0..=5.0),
-                    _ => {let dist = Normal::new(0., 8. / (self.generation+4) as f32 ).unwrap();
+                    _ => {
+                        let sigma = if self.adaptive.0 { self.adaptive.1 } else { 8. / (self.generation+4) as f32 };
+                        let dist = Normal::new(0., sigma ).unwrap();
                         c.weight = (c.weight + dist.sample(&mut rng).max(f32::MIN)).clamp(-9.9, 9.9);
                     }
                 }
@@ -695,6 +813,16 @@ impl NN {
         self.pruning = (enabled, ratio);
     }
 
+    /// Is self-adaptation enabled, and current weight mutation's sigma.
+    pub fn get_adaptive(&self) -> &(bool, f32) {
+        &self.adaptive
+    }
+
+    /// If enabled, chances and weight mutation's sigma are evolved along with network.
+    pub fn set_adaptive(&mut self, enabled: bool, sigma: f32) {
+        self.adaptive = (enabled, sigma);
+    }
+
     pub fn get_chances(&self) -> &[usize; 8] {
         &self.chances
     }
@@ -769,3 +897,23 @@ impl fmt::Debug for NN {
         write!(fmt, "{}", l)
     }
 }
+
+/// Gets innovation number of connection, registers it if it's new.
+pub(crate) fn innov_get(innov_table: &mut HashMap<(NodeKey, NodeKey, bool), usize>, innov_id: &mut usize, 
+    key: (NodeKey, NodeKey, bool)) -> usize {
+    *innov_table.entry(key).or_insert_with(|| { *innov_id += 1; *innov_id - 1 })
+}
+
+/// Mutates network and assigns innovation numbers to new genes.
+pub(crate) fn innov_mutate(innov_table: &mut HashMap<(NodeKey, NodeKey, bool), usize>, innov_id: &mut usize, nn: &mut NN) {
+    let (n_conn, n_node) = nn.mutate();
+    if let Some(c) = n_conn {
+        let correct = innov_get(innov_table, innov_id, (c.from, c.to, c.recurrent));
+        assert!(nn.correct_keys(correct, 0) == 1);
+    }
+    if let Some((c0, c1)) = n_node {
+        let correct0 = innov_get(innov_table, innov_id, (c0.from, c0.to, c0.recurrent));
+        let correct1 = innov_get(innov_table, innov_id, (c1.from, c1.to, c1.recurrent));
+        assert!(nn.correct_keys(correct0, correct1) == 2);
+    }
+}
@@ -0,0 +1,74 @@
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{NeatIntermittent, NN};
+
+/**
+Novelty search settings and archive.
+Agent's novelty is it's sparseness: average distance of it's behaviour
+to k nearest behaviours from current population and archive.
+Useful in deceptive tasks (eg. mazes), where fitness alone leads into dead ends.
+*/
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Novelty {
+    /// Amount of nearest neighbours used for sparseness.
+    pub k: usize,
+    /// Behaviours with sparseness above it are archived.
+    pub threshold: f32,
+    /// Weight of novelty during selection, 1 is pure novelty search, 0 is fitness only.
+    pub weight: f32,
+    /// Maximum archive length, oldest behaviours are removed first. None is unbounded.
+    pub capacity: Option<usize>,
+    /// Archived behaviour characterisations.
+    pub archive: Vec<Vec<f32>>,
+}
+
+impl Novelty {
+    pub fn new(k: usize, threshold: f32, weight: f32) -> Self {
+        Self { k, threshold, weight, capacity: None, archive: vec![] }
+    }
+
+    /// Sparseness of behaviour against given pool (behaviour itself should be excluded from it).
+    pub fn sparseness(&self, behaviour: &[f32], pool: &[&Vec<f32>]) -> f32 {
+        let k = self.k.min(pool.len());
+        if k == 0 {return 0.}
+        pool.iter().map(|b| distance(behaviour, b) ).sorted_by(|a, b| a.total_cmp(b) ).take(k).sum::<f32>() / k as f32
+    }
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip_eq(b.iter()).map(|(a, b)| (a - b) * (a - b) ).sum::<f32>().sqrt()
+}
+
+impl NeatIntermittent {
+    /// Calculates novelty of every agent from it's behaviour characterisation vector,
+    /// and archives sufficiently novel ones. Should be run after evaluation, before next_gen.
+    /// Panics if novelty search is disabled or behaviours length doesn't match population.
+    pub fn novelty_evaluate(&mut self, behaviours: &[Vec<f32>]) {
+        let novelty = self.novelty.as_mut().expect("Novelty search is disabled");
+        if behaviours.len() != self.agents.len() {panic!("Behaviours length doesn't match population")}
+
+        let sparseness: Vec<f32> = behaviours.par_iter().enumerate().map(|(i, b)| {
+            let pool = behaviours.iter().enumerate().filter(|(j, _)| *j != i ).map(|(_, o)| o )
+                .chain(novelty.archive.iter()).collect_vec();
+            novelty.sparseness(b, &pool)
+        }).collect();
+
+        self.agents.iter_mut().zip(sparseness.iter()).for_each(|(a, s)| a.novelty = *s );
+        behaviours.iter().zip(sparseness.iter()).filter(|(_, s)| **s > novelty.threshold )
+            .for_each(|(b, _)| novelty.archive.push(b.clone()) );
+        if let Some(c) = novelty.capacity && novelty.archive.len() > c {
+            novelty.archive.drain(0..novelty.archive.len() - c);
+        }
+    }
+
+    /// Single agent's selection value: fitness, or it's blend with novelty if novelty search is enabled.
+    /// Multi-objective mode isn't taken into account.
+    pub fn score(&self, nn: &NN) -> f32 {
+        match &self.novelty {
+            Some(n) => (1. - n.weight) * nn.fitness + n.weight * nn.novelty,
+            None => nn.fitness,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use itertools::Itertools;
+
+use crate::{NeatIntermittent, NN};
+
+/// True if a is not worse in any objective and better in at least one (higher is better).
+pub fn dominates(a: &[f32], b: &[f32]) -> bool {
+    a.iter().zip_eq(b.iter()).all(|(a, b)| a >= b) && a.iter().zip(b.iter()).any(|(a, b)| a > b)
+}
+
+/// Fast non-dominated sorting (NSGA-II), returns indexes grouped into fronts, from the best.
+pub fn non_dominated_sort(objectives: &[&[f32]]) -> Vec<Vec<usize>> {
+    let n = objectives.len();
+    let mut dominated: Vec<Vec<usize>> = vec![vec![]; n]; // which ones are dominated by i
+    let mut counts = vec![0; n]; // by how many i is dominated
+    for i in 0..n {
+        for j in 0..n {
+            if dominates(objectives[i], objectives[j]) { dominated[i].push(j); }
+            else if dominates(objectives[j], objectives[i]) { counts[i] += 1; }
+        }
+    }
+
+    let mut fronts = vec![];
+    let mut front: Vec<usize> = (0..n).filter(|i| counts[*i] == 0 ).collect();
+    while !front.is_empty() {
+        let mut next = vec![];
+        front.iter().for_each(|i| dominated[*i].iter().for_each(|j| {
+            counts[*j] -= 1;
+            if counts[*j] == 0 { next.push(*j); }
+        }));
+        fronts.push(front);
+        front = next;
+    }
+    fronts
+}
+
+/// Crowding distance of each front member, boundary ones get infinity.
+pub fn crowding_distance(objectives: &[&[f32]], front: &[usize]) -> Vec<f32> {
+    let mut distance = vec![0.; front.len()];
+    if front.len() < 3 { return vec![f32::INFINITY; front.len()] }
+    (0..objectives[front[0]].len()).for_each(|m| {
+        let order = (0..front.len()).sorted_by(|a, b| objectives[front[*a]][m].total_cmp(&objectives[front[*b]][m]) ).collect_vec();
+        let min = objectives[front[order[0]]][m];
+        let range = objectives[front[*order.last().unwrap()]][m] - min;
+        distance[order[0]] = f32::INFINITY;
+        distance[*order.last().unwrap()] = f32::INFINITY;
+        if range <= 0. {return}
+        order.windows(3).for_each(|w| 
+            distance[w[1]] += (objectives[front[w[2]]][m] - objectives[front[w[0]]][m]) / range
+        );
+    });
+    distance
+}
+
+impl NeatIntermittent {
+    /// Selection score of every agent used in next_gen.
+    /// In multi-objective mode it's derived from non-dominated rank and crowding distance:
+    /// better front always wins, inside front less crowded agent is preferred.
+    pub fn scores(&self) -> Vec<f32> {
+        if !self.multi_objective { return self.agents.iter().map(|a| self.score(a) ).collect() }
+
+        let objectives = self.objectives();
+        let fronts = non_dominated_sort(&objectives);
+        let mut scores = vec![0.; self.agents.len()];
+        fronts.iter().enumerate().for_each(|(rank, front)| {
+            crowding_distance(&objectives, front).iter().zip(front.iter()).for_each(|(d, i)| {
+                let crowding = if d.is_finite() { d / (1. + d) } else { 1. }; // squashed into 0..=1
+                scores[*i] = (fronts.len() - rank - 1) as f32 + crowding * 0.99;
+            });
+        });
+        scores
+    }
+
+    /// Indexes of agents that aren't dominated by any other (by objectives).
+    pub fn pareto_front(&self) -> Vec<usize> {
+        non_dominated_sort(&self.objectives()).into_iter().next().unwrap_or_default()
+    }
+
+    /// Panics if agents have different amount of objectives.
+    fn objectives(&self) -> Vec<&[f32]> {
+        let len = self.agents.first().map_or(0, |a| a.objectives.len() );
+        if self.agents.iter().any(|a| a.objectives.len() != len ) {panic!("Agents have different amount of objectives")}
+        self.agents.iter().map(|a: &NN| a.objectives.as_slice() ).collect()
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::NN;
+
+/// Single genome in genealogy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub id: usize,
+    pub parents: Option<(usize, usize)>,
+    /// Species and fitness from the last time agent was recorded.
+    pub species: usize,
+    pub fitness: f32,
+    pub birth: usize,
+}
+
+/**
+Genealogy of whole run, handlers keep only living agents, so it has to be recorded along the way.
+Call record every generation (after evaluation) or every few ticks, it keeps every agent ever seen,
+and species sizes at each call.
+*/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Phylogeny {
+    pub records: HashMap<usize, Record>,
+    /// Time of record call, and (species, size) sorted by species.
+    pub history: Vec<(usize, Vec<(usize, usize)>)>,
+}
+
+impl Phylogeny {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds new agents and updates species and fitness of known ones.
+    /// Time is generation or tick, depending on handler.
+    pub fn record<'a>(&mut self, time: usize, agents: impl IntoIterator<Item = &'a NN>) {
+        let mut sizes: HashMap<usize, usize> = HashMap::new();
+        agents.into_iter().for_each(|a| {
+            *sizes.entry(a.species).or_default() += 1;
+            self.records.insert(a.lineage.id, Record {
+                id: a.lineage.id,
+                parents: a.lineage.parents,
+                species: a.species,
+                fitness: a.fitness,
+                birth: a.lineage.birth,
+            });
+        });
+        self.history.push((time, sizes.into_iter().sorted().collect()));
+    }
+
+    // first parent is treated as tree parent, unknown parents make new root
+    fn children(&self) -> (Vec<usize>, HashMap<usize, Vec<usize>>) {
+        let mut roots = vec![];
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        self.records.values().sorted_by_key(|r| r.id ).for_each(|r| match r.parents {
+            Some((p, _)) if self.records.contains_key(&p) => children.entry(p).or_default().push(r.id),
+            _ => roots.push(r.id),
+        });
+        (roots, children)
+    }
+
+    /// Forest in Newick format, one tree per line. Only first parent is used,
+    /// branch length is time between parent's and child's birth.
+    pub fn newick(&self) -> String {
+        let (roots, children) = self.children();
+        let none = vec![];
+        roots.iter().map(|root| {
+            // iterative post-order, lineages in long runs are too deep for recursion
+            let mut stack = vec![(*root, false)];
+            let mut done: Vec<String> = vec![];
+            while let Some((id, visited)) = stack.pop() {
+                let kids = children.get(&id).unwrap_or(&none);
+                if !visited {
+                    stack.push((id, true));
+                    kids.iter().rev().for_each(|k| stack.push((*k, false)) );
+                    continue;
+                }
+                let r = &self.records[&id];
+                let length = match r.parents {
+                    Some((p, _)) if self.records.contains_key(&p) => r.birth.saturating_sub(self.records[&p].birth),
+                    _ => 0,
+                };
+                let sub = done.split_off(done.len() - kids.len());
+                let node = match sub.is_empty() {
+                    true => format!("{}:{}", id, length),
+                    false => format!("({}){}:{}", sub.join(","), id, length),
+                };
+                done.push(node);
+            }
+            done.pop().unwrap() + ";"
+        }).join("\n")
+    }
+
+    /// Directed graph in DOT format, edges from both parents.
+    pub fn dot(&self) -> String {
+        let mut out = String::from("digraph phylogeny {\n");
+        self.records.values().sorted_by_key(|r| r.id ).for_each(|r| {
+            out += &format!("  {} [label=\"{}\\ns:{} f:{:.2} b:{}\"];\n", r.id, r.id, r.species, r.fitness, r.birth);
+        });
+        self.edges().iter().for_each(|(p, c)| out += &format!("  {} -> {};\n", p, c) );
+        out + "}\n"
+    }
+
+    /// Directed graph in GraphML format, edges from both parents.
+    pub fn graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"species\" for=\"node\" attr.name=\"species\" attr.type=\"int\"/>\n",
+            "  <key id=\"fitness\" for=\"node\" attr.name=\"fitness\" attr.type=\"float\"/>\n",
+            "  <key id=\"birth\" for=\"node\" attr.name=\"birth\" attr.type=\"int\"/>\n",
+            "  <graph id=\"phylogeny\" edgedefault=\"directed\">\n",
+        ));
+        self.records.values().sorted_by_key(|r| r.id ).for_each(|r| {
+            out += &format!(
+                "    <node id=\"n{}\"><data key=\"species\">{}</data><data key=\"fitness\">{}</data><data key=\"birth\">{}</data></node>\n",
+                r.id, r.species, r.fitness, r.birth
+            );
+        });
+        self.edges().iter().for_each(|(p, c)| out += &format!("    <edge source=\"n{}\" target=\"n{}\"/>\n", p, c) );
+        out + "  </graph>\n</graphml>\n"
+    }
+
+    // (parent, child), parents that weren't recorded are skipped
+    fn edges(&self) -> Vec<(usize, usize)> {
+        self.records.values().sorted_by_key(|r| r.id ).flat_map(|r| match r.parents {
+            Some((a, b)) if a == b => vec![a],
+            Some((a, b)) => vec![a, b],
+            None => vec![],
+        }.into_iter().filter(|p| self.records.contains_key(p) ).map(|p| (p, r.id) ).collect_vec() ).collect()
+    }
+
+    /**
+    Included in optional feature "visu".
+    Species-over-time plot: each species is horizontal band, which thickness is it's size at given record.
+    Returns svg, format of saved file is derived from path (svg, png, jpg).
+    */
+    #[cfg(feature = "visu")]
+    pub fn fishbone(&self, save_path: Option<&str>) -> String {
+        use simplesvg as svg;
+
+        let species: Vec<usize> = self.history.iter().flat_map(|(_, s)| s.iter().map(|(k, _)| *k ) ).unique().sorted().collect();
+        let lane: HashMap<usize, usize> = species.iter().enumerate().map(|(i, s)| (*s, i) ).collect();
+        let max = self.history.iter().flat_map(|(_, s)| s.iter().map(|(_, n)| *n ) ).max().unwrap_or(1) as f32;
+        let (step, height, margin) = (8., 48., 64.);
+        let size = (margin + step * self.history.len() as f32 + 16., height * (species.len() + 1) as f32);
+
+        let mut objs: Vec<svg::Fig> = vec![
+            svg::Fig::Rect(0., 0., size.0, size.1).styled(svg::Attr::default().fill(svg::Color(24, 24, 24)))
+        ];
+        species.iter().enumerate().for_each(|(i, s)| {
+            let y = height * (i as f32 + 1.);
+            objs.push(svg::Fig::Line(margin, y, size.0 - 16., y)
+                .styled(svg::Attr::default().stroke(svg::Color(80, 80, 80)).stroke_width(0.5)));
+            objs.push(svg::Fig::Text(8., y + 4., format!("{}", s))
+                .styled(svg::Attr::default().fill(svg::Color(200, 200, 200))));
+        });
+        self.history.iter().enumerate().for_each(|(x, (_, sizes))| {
+            sizes.iter().for_each(|(s, n)| {
+                let (i, h) = (lane[s], *n as f32 / max * (height - 8.));
+                let c = i + 1;
+                let color = svg::Color(((c * 97) % 200 + 55) as u8, ((c * 57) % 200 + 55) as u8, ((c * 151) % 200 + 55) as u8);
+                objs.push(svg::Fig::Rect(margin + step * x as f32, height * (i as f32 + 1.) - h / 2., step, h)
+                    .styled(svg::Attr::default().fill(color)));
+            });
+        });
+
+        let svg = svg::Svg(objs, size.0 as u32, size.1 as u32).to_string();
+        crate::visu::render(&svg, save_path)
+    }
+}
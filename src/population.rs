@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use crate::{nn::{innov_get, innov_mutate}, reshape::{reshape, Change}, ActFunc, NeatContinous, NeatIntermittent, NodeKey, NN};
+
+/**
+Common interface of NeatIntermittent and NeatContinous, so user code and extensions
+can be written once against either handler (agents are Vec in one and HashMap in the other).
+Handlers' own methods with the same names are kept, and behave the same.
+*/
+pub trait Population {
+    fn agents(&self) -> impl Iterator<Item = &NN>;
+    fn agents_mut(&mut self) -> impl Iterator<Item = &mut NN>;
+    /// Agents and every other stored genome, that has to follow I/O changes (eg. hall of fame).
+    fn genomes_mut(&mut self) -> impl Iterator<Item = &mut NN> {
+        self.agents_mut()
+    }
+    /// Innovation table and first free innovation number.
+    fn innovations(&mut self) -> (&mut HashMap<(NodeKey, NodeKey, bool), usize>, &mut usize);
+    /// Assigns all agents to species, and corrects threshold.
+    fn speciate(&mut self);
+    fn species_count(&self) -> usize;
+
+    fn len(&self) -> usize {
+        self.agents().count()
+    }
+    fn is_empty(&self) -> bool {
+        self.agents().next().is_none()
+    }
+    /// Agent with highest fitness.
+    fn champion(&self) -> Option<&NN> {
+        self.agents().max_by(|a, b| a.fitness.total_cmp(&b.fitness) )
+    }
+    /// Returns innovation number of connection (from, to, recurrent), registering it if it's new.
+    fn innov_register(&mut self, key: (NodeKey, NodeKey, bool)) -> usize {
+        let (table, id) = self.innovations();
+        innov_get(table, id, key)
+    }
+    /// Mutates network that isn't part of population (eg. before insert_genome),
+    /// new genes get innovation numbers from handler's table.
+    fn mutate_genome(&mut self, nn: &mut NN) {
+        let (table, id) = self.innovations();
+        innov_mutate(table, id, nn);
+    }
+    /// Adds input to every genome (see genomes_mut).
+    /// Panics if any genome have no free space.
+    fn add_input(&mut self) {
+        if self.genomes_mut().any(|a| a.size_free.0 < 1 ) {panic!("No more space for inputs")}
+        self.genomes_mut().for_each(|a| { a.add_input(); });
+    }
+    /// Adds output to every genome (see genomes_mut).
+    /// Panics if any genome have no free space.
+    fn add_output(&mut self, func: &ActFunc) {
+        if self.genomes_mut().any(|a| a.size_free.1 < 1 ) {panic!("No more space for outputs")}
+        self.genomes_mut().for_each(|a| { a.add_output(func); });
+    }
+    /// Removes input (0 is the first one after bias) from every genome, along with it's connections.
+    /// Following inputs are moved one place back, and freed slot returns to reserve.
+    /// Panics if there is no such input.
+    fn remove_input(&mut self, index: usize) {
+        reshape(self, Change::RemoveInput(index));
+    }
+    /// Removes output from every genome, along with it's connections.
+    /// Following outputs are moved one place back, and freed slot returns to reserve.
+    /// Panics if there is no such output.
+    fn remove_output(&mut self, index: usize) {
+        reshape(self, Change::RemoveOutput(index));
+    }
+    /// Reserves space for more inputs and outputs (see add_input/add_output) in every genome.
+    /// Output keys are moved, innovation numbers that collide with grown I/O range are renumbered.
+    fn grow_reserve(&mut self, inputs: usize, outputs: usize) {
+        reshape(self, Change::Grow(inputs, outputs));
+    }
+    /// If enabled each mutation deletes node or connection.
+    /// Ratio of 0.1 means, that there is 10% chance of deleting node
+    /// , and 90% of deleting connection.
+    fn set_pruning(&mut self, enabled: bool, ratio: f32) {
+        self.agents_mut().for_each(|a| a.set_pruning(enabled, ratio) );
+    }
+    /// If enabled each agent's chances and weight mutation's sigma (starting from given one) 
+    /// are mutated along with it, and inherited in crossover.
+    fn set_adaptive(&mut self, enabled: bool, sigma: f32) {
+        self.agents_mut().for_each(|a| a.set_adaptive(enabled, sigma) );
+    }
+}
+
+impl Population for NeatIntermittent {
+    fn agents(&self) -> impl Iterator<Item = &NN> {
+        self.agents.iter()
+    }
+    fn agents_mut(&mut self) -> impl Iterator<Item = &mut NN> {
+        self.agents.iter_mut()
+    }
+    fn genomes_mut(&mut self) -> impl Iterator<Item = &mut NN> {
+        self.agents.iter_mut().chain(self.hall_of_fame.genomes.iter_mut())
+    }
+    fn innovations(&mut self) -> (&mut HashMap<(NodeKey, NodeKey, bool), usize>, &mut usize) {
+        (&mut self.innov_table, &mut self.innov_id)
+    }
+    fn speciate(&mut self) {
+        NeatIntermittent::speciate(self)
+    }
+    fn species_count(&self) -> usize {
+        self.species_table.len()
+    }
+}
+
+impl Population for NeatContinous {
+    fn agents(&self) -> impl Iterator<Item = &NN> {
+        self.agents.values()
+    }
+    fn agents_mut(&mut self) -> impl Iterator<Item = &mut NN> {
+        self.agents.values_mut()
+    }
+    fn genomes_mut(&mut self) -> impl Iterator<Item = &mut NN> {
+        self.agents.values_mut().chain(self.species_table.values_mut().filter_map(|s| s.representative.as_mut() ))
+    }
+    fn innovations(&mut self) -> (&mut HashMap<(NodeKey, NodeKey, bool), usize>, &mut usize) {
+        (&mut self.innov_table, &mut self.innov_id)
+    }
+    fn speciate(&mut self) {
+        NeatContinous::speciate(self)
+    }
+    fn species_count(&self) -> usize {
+        self.species_table.len()
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fs::{self, File, OpenOptions}, io::Write, path::PathBuf, time::Instant};
+use itertools::Itertools;
+
+use crate::stats::{percentile, GenerationStats, Observer};
+
+/// Output file format of Reporter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Comma separated values, header is written if file is empty.
+    Csv,
+    /// Each row is separate JSON object.
+    JsonLines,
+}
+
+const COLUMNS: [&str; 21] = [
+    "generation", "time", "time_gen",
+    "fitness_min", "fitness_p25", "fitness_median", "fitness_p75", "fitness_max", "fitness_mean",
+    "species", "species_sizes", "species_threshold",
+    "nodes_min", "nodes_median", "nodes_max", "connections_min", "connections_median", "connections_max",
+    "innovations", "chances_mean", "sigma_mean",
+];
+
+/**
+Observer appending one row per generation to CSV or JSON-lines file.
+Row is written after speciation (fitness is still the evaluated one), 
+repeated speciation in the same generation is ignored.
+Optionally saves champion network every N generations.
+*/
+pub struct Reporter {
+    file: File,
+    format: ReportFormat,
+    start: Instant,
+    last: Instant,
+    last_generation: Option<usize>,
+    champion_dump: Option<(usize, PathBuf)>,
+}
+
+impl Reporter {
+    /// Opens file in append mode.
+    pub fn new(path: &str, format: ReportFormat) -> Self {
+        let mut file = OpenOptions::new().create(true).append(true).open(path).unwrap();
+        if format == ReportFormat::Csv && file.metadata().unwrap().len() == 0 {
+            file.write_all((COLUMNS.join(",") + "\n").as_bytes()).unwrap();
+        }
+        Self { file, format, start: Instant::now(), last: Instant::now(), last_generation: None, champion_dump: None }
+    }
+
+    /// Every "every" generations champion is saved into directory as `champion_<generation>.toml`.
+    pub fn champion_dump(mut self, every: usize, dir: &str) -> Self {
+        fs::create_dir_all(dir).unwrap();
+        self.champion_dump = Some((every.max(1), PathBuf::from(dir)));
+        self
+    }
+
+    fn row(&self, stats: &GenerationStats) -> Vec<String> {
+        let now = Instant::now();
+        vec![
+            stats.generation.to_string(),
+            format!("{:.3}", (now - self.start).as_secs_f32()),
+            format!("{:.3}", (now - self.last).as_secs_f32()),
+            percentile(&stats.fitness, 0.).to_string(),
+            percentile(&stats.fitness, 0.25).to_string(),
+            stats.fitness_median.to_string(),
+            percentile(&stats.fitness, 0.75).to_string(),
+            stats.fitness_best.to_string(),
+            stats.fitness_mean.to_string(),
+            stats.species.len().to_string(),
+            stats.species.iter().map(|s| s.size ).join(";"),
+            stats.species_threshold.to_string(),
+            percentile(&stats.nodes, 0.).to_string(),
+            percentile(&stats.nodes, 0.5).to_string(),
+            percentile(&stats.nodes, 1.).to_string(),
+            percentile(&stats.connections, 0.).to_string(),
+            percentile(&stats.connections, 0.5).to_string(),
+            percentile(&stats.connections, 1.).to_string(),
+            stats.innovations.to_string(),
+            stats.chances_mean.iter().map(|c| format!("{:.2}", c) ).join(";"),
+            stats.sigma_mean.to_string(),
+        ]
+    }
+}
+
+impl Observer for Reporter {
+    fn speciated(&mut self, stats: &GenerationStats) {
+        if self.last_generation == Some(stats.generation) {return}
+        let row = self.row(stats);
+        let line = match self.format {
+            ReportFormat::Csv => row.join(","),
+            ReportFormat::JsonLines => "{".to_string() + &COLUMNS.iter().zip(row.iter()).map(|(c, v)| {
+                if *c == "species_sizes" || *c == "chances_mean" { format!("\"{}\":[{}]", c, v.replace(';', ",")) }
+                else if v == "NaN" || v.ends_with("inf") { format!("\"{}\":null", c) }
+                else { format!("\"{}\":{}", c, v) }
+            }).join(",") + "}",
+        };
+        self.file.write_all((line + "\n").as_bytes()).unwrap();
+
+        if let Some((every, dir)) = &self.champion_dump && stats.generation.is_multiple_of(*every) {
+            stats.champion.save(dir.join(format!("champion_{}.toml", stats.generation)).to_str().unwrap());
+        }
+        self.last_generation = Some(stats.generation);
+        self.last = Instant::now();
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::{BTreeSet, HashMap};
+use itertools::Itertools;
+
+use crate::{node::NodeKey, Population};
+
+// change of network's I/O layout
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Change {
+    RemoveInput(usize),
+    RemoveOutput(usize),
+    /// Additional reserved space for inputs and outputs.
+    Grow(usize, usize),
+}
+
+// new keys of I/O nodes (None if node is removed), new size and size_free
+#[allow(clippy::type_complexity)]
+fn io_map(size: (usize, usize), size_free: (usize, usize), change: Change) -> (HashMap<NodeKey, Option<NodeKey>>, (usize, usize), (usize, usize)) {
+    let (new_size, new_free) = match change {
+        Change::RemoveInput(_) => ((size.0 - 1, size.1), (size_free.0 + 1, size_free.1)),
+        Change::RemoveOutput(_) => ((size.0, size.1 - 1), (size_free.0, size_free.1 + 1)),
+        Change::Grow(i, o) => (size, (size_free.0 + i, size_free.1 + o)),
+    };
+    let mut map = HashMap::new();
+    // bias is input 0, so input "index" has key index + 1
+    let mut j = 0;
+    for i in 0..size.0 {
+        if i > 0 && change == Change::RemoveInput(i - 1) { map.insert(NodeKey::new(i, 0), None); continue; }
+        map.insert(NodeKey::new(i, 0), Some(NodeKey::new(j, 0)));
+        j += 1;
+    }
+    let (old_o, new_o) = (size.0 + size_free.0, new_size.0 + new_free.0);
+    let mut j = 0;
+    for i in 0..size.1 {
+        if change == Change::RemoveOutput(i) { map.insert(NodeKey::new(old_o + i, 0), None); continue; }
+        map.insert(NodeKey::new(old_o + i, 0), Some(NodeKey::new(new_o + j, 0)));
+        j += 1;
+    }
+    (map, new_size, new_free)
+}
+
+/// Applies I/O change to every genome of handler and to it's innovation table.
+/// Connection's innovation numbers stay the same, unless they fall into (grown) I/O key range,
+/// then they are moved above it, along with hidden nodes that split them.
+pub(crate) fn reshape<P: Population + ?Sized>(population: &mut P, change: Change) {
+    let agent = population.agents().next().unwrap();
+    let (size, size_free) = (agent.size, agent.size_free);
+    match change {
+        Change::RemoveInput(i) if i + 1 >= size.0 => panic!("No input with index {}", i),
+        Change::RemoveOutput(o) if o >= size.1 => panic!("No output with index {}", o),
+        Change::RemoveOutput(_) if size.1 == 1 => panic!("Can't remove the only output"),
+        _ => {}
+    }
+    let (io, new_size, new_free) = io_map(size, size_free, change);
+    let end = new_size.0 + new_free.0 + new_size.1 + new_free.1; // first key above reserved I/O range
+
+    // connection ids and hidden nodes' keys, even if table doesn't have them
+    let used: BTreeSet<usize> = population.genomes_mut().flat_map(|g| g.connections.keys().cloned()
+        .chain(g.nodes.keys().filter(|k| !io.contains_key(k) ).map(|k| k.sconn )).collect_vec() ).collect();
+    let (table, innov_id) = population.innovations();
+    *innov_id = (*innov_id).max(end + 1);
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    table.values().cloned().chain(used).filter(|id| *id <= end ).sorted().dedup().for_each(|id| {
+        ids.insert(id, *innov_id);
+        *innov_id += 1;
+    });
+    let node = |k: &NodeKey| match io.get(k) {
+        Some(n) => n.clone(),
+        None => Some(NodeKey::new(*ids.get(&k.sconn).unwrap_or(&k.sconn), k.dup)),
+    };
+
+    *table = table.drain().filter_map(|((from, to, recurrent), id)|
+        Some(((node(&from)?, node(&to)?, recurrent), *ids.get(&id).unwrap_or(&id)))
+    ).collect();
+    population.genomes_mut().for_each(|g| g.relocate(&node, &ids, new_size, new_free) );
+}
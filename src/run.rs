@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+use rayon::prelude::*;
+
+use crate::{NeatIntermittent, NN};
+
+/// Task that networks are evolved to solve.
+/// Implemented by user, so evolution loop doesn't need to be written by hand.
+pub trait Problem: Sync {
+    /// Runs single network through the task and returns it's fitness.
+    /// Fitness should be positive, higher is better.
+    fn evaluate(&self, nn: &mut NN) -> f32;
+}
+
+/// Conditions that stop `NeatIntermittent::run`, None means that criterion is disabled.
+/// If all are disabled, evolution never stops.
+#[derive(Debug, Clone, Default)]
+pub struct Termination {
+    /// Stop when champion reaches this fitness.
+    pub fitness: Option<f32>,
+    /// Maximum amount of generations.
+    pub generations: Option<usize>,
+    /// Maximum wall-clock time.
+    pub time: Option<Duration>,
+    /// Stop after this many generations without champion improvement.
+    pub stagnation: Option<usize>,
+}
+
+impl Termination {
+    /// Returns reason to stop, if any criterion is met.
+    pub fn check(&self, fitness: f32, generation: usize, elapsed: Duration, stagnation: usize) -> Option<Stop> {
+        if self.fitness.is_some_and(|f| fitness >= f) {Some(Stop::Fitness)}
+        else if self.generations.is_some_and(|g| generation >= g) {Some(Stop::Generations)}
+        else if self.time.is_some_and(|t| elapsed >= t) {Some(Stop::Time)}
+        else if self.stagnation.is_some_and(|s| stagnation >= s) {Some(Stop::Stagnation)}
+        else {None}
+    }
+}
+
+/// Reason why run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Fitness,
+    Generations,
+    Time,
+    Stagnation,
+}
+
+impl NeatIntermittent {
+    /// Evaluates all agents in parallel, assigning their fitness.
+    pub fn evaluate<P: Problem + ?Sized>(&mut self, problem: &P) {
+        self.agents.par_iter_mut().for_each(|a| a.fitness = problem.evaluate(a) );
+    }
+
+    /// Evolves population until one of termination criteria is met.
+    /// Each generation agents are evaluated, then speciated, reproduced and mutated.
+    /// After return agents are left evaluated, returned network is the best one ever seen.
+    pub fn run<P: Problem>(&mut self, problem: &P, termination: &Termination) -> (Stop, NN) {
+        let start = Instant::now();
+        let mut champion: Option<NN> = None;
+        let mut stagnation = 0;
+        let mut generation = 0;
+
+        loop {
+            self.evaluate(problem);
+            generation += 1;
+
+            let best = self.agents.iter().max_by(|a, b| a.fitness.total_cmp(&b.fitness) ).unwrap();
+            match &champion {
+                Some(c) if c.fitness >= best.fitness => stagnation += 1,
+                _ => { champion = Some(best.clone()); stagnation = 0; }
+            }
+            let fitness = champion.as_ref().unwrap().fitness;
+
+            if let Some(stop) = termination.check(fitness, generation, start.elapsed(), stagnation) {
+                return (stop, champion.unwrap())
+            }
+
+            self.speciate();
+            self.next_gen();
+            self.mutate(None);
+        }
+    }
+}
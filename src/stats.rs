@@ -0,0 +1,95 @@
+use itertools::Itertools;
+
+use crate::{NeatIntermittent, NN};
+
+/// Snapshot of single species.
+#[derive(Debug, Clone)]
+pub struct SpeciesStats {
+    pub id: usize,
+    pub size: usize,
+    pub offspring: usize,
+    /// Average fitness calculated in last next_gen.
+    pub fitness: f32,
+}
+
+/// Snapshot of population, passed to observers.
+#[derive(Debug, Clone)]
+pub struct GenerationStats<'a> {
+    pub generation: usize,
+    pub fitness_best: f32,
+    pub fitness_mean: f32,
+    pub fitness_median: f32,
+    /// Fitness of every agent, sorted ascending.
+    pub fitness: Vec<f32>,
+    /// Sorted by species id.
+    pub species: Vec<SpeciesStats>,
+    pub species_threshold: f32,
+    /// Desired amount of species.
+    pub species_amount: usize,
+    pub nodes_mean: f32,
+    pub connections_mean: f32,
+    /// Node count of every agent, sorted ascending.
+    pub nodes: Vec<usize>,
+    /// Connection count of every agent, sorted ascending.
+    pub connections: Vec<usize>,
+    /// Average mutation chances, they drift if self-adaptation is enabled.
+    pub chances_mean: [f32; 8],
+    /// Average weight mutation's sigma of self-adaptive agents (0 if there are none).
+    pub sigma_mean: f32,
+    /// Amount of registered innovations.
+    pub innovations: usize,
+    /// Agent with highest fitness.
+    pub champion: &'a NN,
+}
+
+impl<'a> GenerationStats<'a> {
+    /// Panics if population is empty.
+    pub fn collect(handler: &'a NeatIntermittent) -> Self {
+        let agents = &handler.agents;
+        let n = agents.len() as f32;
+        let fitness: Vec<f32> = agents.iter().map(|a| a.fitness ).sorted_by(|a, b| a.total_cmp(b) ).collect();
+        let median = match fitness.len() % 2 {
+            0 => (fitness[fitness.len()/2 - 1] + fitness[fitness.len()/2]) / 2.,
+            _ => fitness[fitness.len()/2],
+        };
+
+        Self {
+            generation: handler.generation,
+            fitness_best: *fitness.last().unwrap(),
+            fitness_mean: fitness.iter().sum::<f32>() / n,
+            fitness_median: median,
+            fitness,
+            species: handler.species_table.iter().sorted_by_key(|(k,_)| **k )
+                .map(|(k, s)| SpeciesStats { id: *k, size: s.size, offspring: s.offspring, fitness: s.fitness() } ).collect(),
+            species_threshold: handler.species_threshold,
+            species_amount: handler.species_amount,
+            nodes_mean: agents.iter().map(|a| a.nodes.len() ).sum::<usize>() as f32 / n,
+            connections_mean: agents.iter().map(|a| a.connections.len() ).sum::<usize>() as f32 / n,
+            nodes: agents.iter().map(|a| a.nodes.len() ).sorted().collect(),
+            connections: agents.iter().map(|a| a.connections.len() ).sorted().collect(),
+            chances_mean: std::array::from_fn(|i| agents.iter().map(|a| a.get_chances()[i] as f32 ).sum::<f32>() / n ),
+            sigma_mean: match agents.iter().filter(|a| a.get_adaptive().0 ).count() {
+                0 => 0.,
+                c => agents.iter().filter(|a| a.get_adaptive().0 ).map(|a| a.get_adaptive().1 ).sum::<f32>() / c as f32,
+            },
+            innovations: handler.innov_table.len(),
+            champion: agents.iter().max_by(|a, b| a.fitness.total_cmp(&b.fitness) ).unwrap(),
+        }
+    }
+}
+
+/// Receives population snapshots from NeatIntermittent.
+/// Each method is called after corresponding handler's step, default implementations do nothing.
+pub trait Observer {
+    /// After speciate.
+    fn speciated(&mut self, _stats: &GenerationStats) {}
+    /// After next_gen, fitness of new agents is inherited from fitter parent.
+    fn reproduced(&mut self, _stats: &GenerationStats) {}
+    /// After mutation of whole population.
+    fn mutated(&mut self, _stats: &GenerationStats) {}
+}
+
+/// Value at given fraction (0..=1) of sorted slice, nearest rank.
+pub fn percentile<T: Copy>(sorted: &[T], p: f32) -> T {
+    sorted[((sorted.len() - 1) as f32 * p.clamp(0., 1.)).round() as usize]
+}
@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+
+/// How species threshold reacts to difference between target and current amount of species.
+/// Error is relative: (target - species) / target, positive error lowers threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdControl {
+    /// Threshold never changes.
+    Fixed,
+    /// Constant step, if amount of species is further than deadband from target.
+    Step { step: f32, deadband: usize },
+    /// Step proportional to error.
+    Proportional { kp: f32 },
+    /// Proportional plus integral of error, removes steady offset that proportional one leaves.
+    /// Integral isn't accumulated while step or threshold is clamped (anti-windup).
+    Pi { kp: f32, ki: f32 },
+}
+
+/**
+Corrects species threshold to hit target amount of species, shared by both handlers.
+Single step is clamped to max_step, and threshold itself to bounds.
+Continous handler uses small steps (it's corrected after every birth), intermittent proportional control.
+*/
+#[derive(Debug, Clone)]
+pub struct ThresholdController {
+    pub control: ThresholdControl,
+    pub max_step: f32,
+    pub bounds: (f32, f32),
+    /// Threshold after each correction, the oldest are dropped above history_len.
+    pub history: VecDeque<f32>,
+    pub history_len: usize,
+    integral: f32,
+}
+
+impl ThresholdController {
+    /// History keeps 1000 last corrections.
+    pub fn new(control: ThresholdControl, max_step: f32, bounds: (f32, f32)) -> Self {
+        Self { control, max_step, bounds, history: VecDeque::new(), history_len: 1000, integral: 0. }
+    }
+
+    /// Returns corrected threshold.
+    pub fn update(&mut self, threshold: f32, species: usize, target: usize) -> f32 {
+        let error = (target as f32 - species as f32) / target.max(1) as f32;
+        let delta = match self.control {
+            ThresholdControl::Fixed => 0.,
+            ThresholdControl::Step { step, deadband } => match target.abs_diff(species) > deadband {
+                true => step * error.signum(),
+                false => 0.,
+            },
+            ThresholdControl::Proportional { kp } => kp * error,
+            ThresholdControl::Pi { kp, ki } => {
+                let delta = kp * error + ki * (self.integral + error);
+                let raw = threshold - delta;
+                let saturated = delta.abs() > self.max_step || raw < self.bounds.0 || raw > self.bounds.1;
+                // integrating is fine if it pulls out of saturation
+                if !saturated || error * delta < 0. { self.integral += error; }
+                delta
+            }
+        };
+        let threshold = (threshold - delta.clamp(-self.max_step, self.max_step)).clamp(self.bounds.0, self.bounds.1);
+
+        self.history.push_back(threshold);
+        if self.history.len() > self.history_len { self.history.pop_front(); }
+        threshold
+    }
+
+    /// Clears integral term, eg. after target was changed.
+    pub fn reset(&mut self) {
+        self.integral = 0.;
+    }
+}
@@ -73,14 +73,18 @@ pub fn visu(nn: &NN, save_path: Option<&str>) -> String {
     objs.insert(0, bg);
 
     let svg = svg::Svg{0: objs, 1: size.0 as u32, 2: size.1 as u32}.to_string();
+    render(&svg, save_path)
+}
 
+// converts text to paths, and saves in format derived from path
+pub(crate) fn render(svg: &str, save_path: Option<&str>) -> String {
     let mut database = usvg::fontdb::Database::default();
     database.load_system_fonts();
     let mut options = usvg::Options::default();
     options.font_family = "FiraCode Nerd Font Mono".to_string();
     let xml_options = usvg::XmlOptions::default();
 
-    let mut tree = usvg::Tree::from_str(&svg, &options).unwrap();
+    let mut tree = usvg::Tree::from_str(svg, &options).unwrap();
     tree.convert_text(&database);
     let out = tree.to_string(&xml_options);
 
@@ -99,4 +103,3 @@ pub fn visu(nn: &NN, save_path: Option<&str>) -> String {
     }
     out
 }
-